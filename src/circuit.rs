@@ -0,0 +1,177 @@
+//! the sort circuit laid out as a comparator network, and what it exposes
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::MockProver,
+    plonk::*,
+};
+use poseidon::Poseidon;
+use crate::compare::{bias, Encoding, Order, RANGE_BITS};
+use crate::limb::field_to_bn;
+use crate::network::SortingNetwork;
+use crate::poseidon_chip::{R_F, R_P};
+use crate::sort::{sentinel, Commitment, MainChip, MainConfig, SortInstructions, POSEIDON_RATE, POSEIDON_T};
+
+// no circuit of this crate should need more than 2^MAX_K rows
+pub const MAX_K: u32 = 26;
+
+/// the smallest k such that every region, the lookup tables, the instance column
+/// and the blinding rows of circuit fit in 2^k rows, use it for both MockProver and keygen
+/// any other synthesis error, or a circuit that does not fit in 2^MAX_K rows, is returned
+pub fn minimal_k<F: FieldExt, C: Circuit<F>>(circuit: &C, instances: &[Vec<F>]) -> Result<u32, Error> {
+    // the range table alone takes 2^RANGE_BITS rows
+    let mut k = RANGE_BITS as u32 + 1;
+    loop {
+        match MockProver::run(k, circuit, instances.to_vec()) {
+            Ok(_) => return Ok(k),
+            // only these two mean that the usable rows are too few
+            Err(Error::NotEnoughRowsAvailable { .. }) | Err(Error::InstanceTooLarge) if k < MAX_K => k += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// whether an array is exposed on the instance column, kept as a private witness,
+/// or kept private with only its poseidon digest exposed
+/// the digest is not hiding: it is unsalted, so an array of small integers can be
+/// recovered by hashing candidates until one matches
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Private,
+    Committed,
+}
+
+/// the poseidon digest the circuit exposes for a committed array
+pub fn poseidon_digest<F: FieldExt>(values: &[F]) -> F {
+    let mut hasher = Poseidon::<F, POSEIDON_T, POSEIDON_RATE>::new(R_F, R_P);
+    hasher.update(values);
+    hasher.squeeze()
+}
+
+/// N is the maximum length of the array, BITS is the bit-width of the elements,
+/// every element has to be below 2^BITS, or in [-2^(BITS - 1), 2^(BITS - 1)) if SIGNED,
+/// where a negative -x is the field element p - x
+/// DESCENDING sorts the largest element first, DESCENDING and SIGNED change the
+/// constraints and the keys
+/// only the first len slots of arr are real, the rest hold the sentinel, the largest
+/// value in ascending order and the smallest in descending order, which sorts to the end
+/// the instance column holds len on row 0, followed by the N padded input slots
+/// if input is public or their digest if it is committed, followed by the N sorted
+/// slots or their digest in the same way for output
+/// network decides which comparators are laid out, bubble sort by default
+/// COMMITTED configures the poseidon sponge, which a committed input or output needs,
+/// arr and len are the witness, None while generating the keys,
+/// the layout only depends on N, BITS, DESCENDING, SIGNED, COMMITTED, the visibility and the network
+#[derive(Debug)]
+pub struct BubSortCircuit<F, const N: usize, const BITS: usize = 8, const DESCENDING: bool = false, const SIGNED: bool = false, const COMMITTED: bool = false> {
+    pub arr: Option<[F; N]>,
+    pub len: Option<usize>,
+    pub input: Visibility,
+    pub output: Visibility,
+    pub network: SortingNetwork,
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> Default for BubSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    fn default() -> Self {
+        Self {
+            arr: None,
+            len: None,
+            input: Visibility::Private,
+            output: Visibility::Private,
+            network: SortingNetwork::Bubble,
+        }
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> BubSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    pub fn order() -> Order {
+        if DESCENDING { Order::Descending } else { Order::Ascending }
+    }
+
+    pub fn encoding() -> Encoding {
+        if SIGNED { Encoding::Signed } else { Encoding::Unsigned }
+    }
+
+    pub fn commitment() -> Commitment {
+        if COMMITTED { Commitment::Poseidon } else { Commitment::Disabled }
+    }
+
+    /// the value every unused slot holds
+    pub fn sentinel() -> F {
+        sentinel::<F>(BITS, Self::order(), Self::encoding())
+    }
+
+    /// fill the first values.len() slots with values and pad the rest with the sentinel
+    pub fn padded(values: &[F]) -> Self {
+        assert!(values.len() <= N, "{} elements do not fit in {} slots", values.len(), N);
+        let mut arr = [Self::sentinel(); N];
+        arr[..values.len()].copy_from_slice(values);
+        Self { arr: Some(arr), len: Some(values.len()), ..Self::default() }
+    }
+
+    /// the padded input in sorted order
+    pub fn sorted(&self) -> Option<[F; N]> {
+        let bias = bias::<F>(BITS, Self::encoding());
+        self.arr.map(|mut sorted| {
+            sorted.sort_by_key(|x| field_to_bn(&(*x + bias)));
+            if DESCENDING {
+                sorted.reverse();
+            }
+            sorted
+        })
+    }
+
+    /// the content of the instance column matching the visibility of input and output
+    pub fn public_inputs(&self) -> Vec<F> {
+        let arr = self.arr.expect("public inputs need the witness");
+        let len = self.len.expect("public inputs need the witness");
+        let mut public_inputs = vec![F::from(len as u64)];
+        for (visibility, values) in [(self.input, arr), (self.output, self.sorted().unwrap())] {
+            match visibility {
+                Visibility::Public => public_inputs.extend_from_slice(&values),
+                Visibility::Committed => public_inputs.push(poseidon_digest(&values)),
+                Visibility::Private => (),
+            }
+        }
+        public_inputs
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> Circuit<F> for BubSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    type Config = MainConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // the visibility and the network decide the copy constraints
+        Self { input: self.input, output: self.output, network: self.network, ..Self::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MainChip::configure(meta, BITS, Self::order(), Self::encoding(), Self::commitment())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        // rows in the table
+        let (v, len) = chip.load_array(
+            layouter.namespace(|| "first row"),
+            self.arr,
+            self.len,
+        )?;
+        let mut instance_row = 0;
+        layouter.constrain_instance(len.cell.unwrap().cell(), config.instance, instance_row)?;
+        instance_row += 1;
+        chip.expose(&mut layouter, &v, self.input, &mut instance_row)?;
+        let sorted = chip.sort(layouter.namespace(|| "sort"), &v, self.network)?;
+        chip.expose(&mut layouter, &sorted, self.output, &mut instance_row)?;
+        Ok(())
+    }
+
+}
@@ -0,0 +1,160 @@
+//! the setup, prove and verify subcommands of the binary
+
+use std::fs;
+use std::path::Path;
+use halo2_proofs::pairing::bn256::Fr;
+use num_bigint::BigUint;
+use halo2_bubble_sort::circuit::{minimal_k, BubSortCircuit, Visibility};
+use halo2_bubble_sort::limb::{bn_to_field, field_to_bn};
+use halo2_bubble_sort::{prover, serialization};
+
+// the shape every file of the command line tool is made for, shorter arrays are padded
+pub const CLI_N: usize = 32;
+pub const CLI_BITS: usize = 64;
+
+type CliCircuit = BubSortCircuit<Fr, CLI_N, CLI_BITS, false, false, true>;
+
+pub const USAGE: &str = "usage:
+    halo2_bubble_sort setup <dir>                             write params and verifying key to <dir>,
+                                                              the params come from an insecure local setup
+    halo2_bubble_sort prove <dir> <input> <proof> <public>    sort the array in <input> (.json or .csv)
+    halo2_bubble_sort verify <dir> <proof> <public>           check <proof> against <public>";
+
+/// the input is committed to by its digest, the sorted output is public
+fn circuit(values: &[Fr]) -> CliCircuit {
+    let mut circuit = CliCircuit::padded(values);
+    circuit.input = Visibility::Committed;
+    circuit.output = Visibility::Public;
+    circuit
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["setup", dir] => setup(Path::new(dir)),
+        ["prove", dir, input, proof, public] => prove(Path::new(dir), Path::new(input), Path::new(proof), Path::new(public)),
+        ["verify", dir, proof, public] => verify(Path::new(dir), Path::new(proof), Path::new(public)),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+// prover::setup samples the toxic waste on this machine, whoever ran it can forge proofs
+const UNSAFE_SETUP_WARNING: &str = "WARNING: the params come from prover::setup, an insecure local setup for testing.
+Whoever ran the setup can forge proofs against these keys, do not use them to convince anyone else.";
+
+fn setup(dir: &Path) -> Result<(), String> {
+    eprintln!("{}", UNSAFE_SETUP_WARNING);
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    // any witness gives the same k, the layout does not depend on it
+    let sample = circuit(&[]);
+    let k = minimal_k(&sample, &[sample.public_inputs()]).map_err(|e| format!("{:?}", e))?;
+    let params = prover::setup(k);
+    let pk = prover::keygen(&params, &sample.without_witnesses()).map_err(|e| format!("{:?}", e))?;
+    serialization::write_params(dir.join("params.bin"), &params).map_err(|e| e.to_string())?;
+    serialization::write_vk::<CliCircuit>(dir.join("vk.bin"), pk.get_vk()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn prove(dir: &Path, input: &Path, proof_path: &Path, public_path: &Path) -> Result<(), String> {
+    let values = read_array(input)?;
+    if values.len() > CLI_N {
+        return Err(format!("{} elements do not fit in {} slots", values.len(), CLI_N));
+    }
+    let circuit = circuit(&values);
+    let public_inputs = circuit.public_inputs();
+
+    let params = serialization::read_params(dir.join("params.bin")).map_err(|e| e.to_string())?;
+    // the proving key is not stored, keygen is deterministic for the same params
+    let pk = prover::keygen(&params, &circuit.without_witnesses()).map_err(|e| format!("{:?}", e))?;
+    let proof = prover::prove(&params, &pk, circuit, &public_inputs).map_err(|e| format!("{:?}", e))?;
+
+    serialization::write_proof::<CliCircuit>(proof_path, &proof).map_err(|e| e.to_string())?;
+    write_public_inputs(public_path, &public_inputs)?;
+    // the first len slots of the output are the sorted input
+    let sorted: Vec<String> = public_inputs[2..2 + values.len()].iter().map(|x| field_to_bn(x).to_string()).collect();
+    println!("[{}]", sorted.join(", "));
+    Ok(())
+}
+
+fn verify(dir: &Path, proof_path: &Path, public_path: &Path) -> Result<(), String> {
+    let params = serialization::read_params(dir.join("params.bin")).map_err(|e| e.to_string())?;
+    let vk = serialization::read_vk::<CliCircuit>(dir.join("vk.bin"), &params).map_err(|e| e.to_string())?;
+    let proof = serialization::read_proof::<CliCircuit>(proof_path).map_err(|e| e.to_string())?;
+    let public_inputs = read_public_inputs(public_path)?;
+    prover::verify(&params, &vk, &proof, &public_inputs).map_err(|e| format!("invalid proof: {:?}", e))?;
+    println!("ok");
+    Ok(())
+}
+
+/// decimal integers below 2^CLI_BITS, as a json array of numbers or strings,
+/// or separated by commas and newlines for any other extension
+fn read_array(path: &Path) -> Result<Vec<Fr>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let tokens: Vec<String> = if path.extension().is_some_and(|ext| ext == "json") {
+        let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        json.as_array()
+            .ok_or("the json input has to be an array")?
+            .iter()
+            .map(|x| match x {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect()
+    } else {
+        content.split([',', '\n'])
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+    tokens.iter().map(|token| {
+        let bn: BigUint = token.parse().map_err(|_| format!("not an unsigned integer: {}", token))?;
+        if bn.bits() > CLI_BITS as u64 {
+            return Err(format!("{} does not fit in {} bits", token, CLI_BITS));
+        }
+        bn_to_field(&bn).ok_or_else(|| format!("{} is not a field element", bn))
+    }).collect()
+}
+
+/// the content of the instance column, as a json array of decimal strings
+fn write_public_inputs(path: &Path, public_inputs: &[Fr]) -> Result<(), String> {
+    let values: Vec<String> = public_inputs.iter().map(|x| field_to_bn(x).to_string()).collect();
+    let json = serde_json::to_string_pretty(&values).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn read_public_inputs(path: &Path) -> Result<Vec<Fr>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let values: Vec<String> = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    values.iter().map(|value| {
+        let bn: BigUint = value.parse().map_err(|_| format!("not an unsigned integer: {}", value))?;
+        bn_to_field(&bn).ok_or_else(|| format!("{} is not a field element", bn))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prove_and_verify_through_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        fs::write(path("input.json"), "[100, 90, 80, 70, 66]").unwrap();
+        fs::write(path("input.csv"), "100,90\n80,70,66\n").unwrap();
+        run(&["setup".to_string(), path("keys")]).unwrap();
+        for input in [path("input.json"), path("input.csv")] {
+            run(&["prove".to_string(), path("keys"), input, path("cli.proof"), path("public.json")]).unwrap();
+            run(&["verify".to_string(), path("keys"), path("cli.proof"), path("public.json")]).unwrap();
+        }
+
+        // an output that is not the sort of the committed input is rejected
+        let public = fs::read_to_string(path("public.json")).unwrap();
+        fs::write(path("public.json"), public.replacen("\"66\"", "\"67\"", 1)).unwrap();
+        assert!(run(&["verify".to_string(), path("keys"), path("cli.proof"), path("public.json")]).is_err());
+    }
+
+    #[test]
+    fn unknown_arguments_print_the_usage() {
+        assert_eq!(run(&[]), Err(USAGE.to_string()));
+        assert_eq!(run(&["sort".to_string()]), Err(USAGE.to_string()));
+    }
+}
@@ -0,0 +1,433 @@
+//! the range table and the comparator built on it
+
+use std::marker::PhantomData;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+use num_bigint::BigUint;
+use crate::limb::{field_to_bn, pow_of_two, Limb};
+
+// number of bits proven by a single lookup into the range table
+pub const RANGE_BITS: usize = 8;
+
+/// the lowest count RANGE_BITS-bit limbs of bn, little endian
+pub fn limb_values<F: FieldExt>(bn: &BigUint, count: usize) -> Vec<F> {
+    let mask = BigUint::from((1u64 << RANGE_BITS) - 1);
+    (0..count)
+        .map(|i| {
+            let limb = (bn >> (i * RANGE_BITS)) & &mask;
+            F::from(limb.to_u64_digits().first().copied().unwrap_or(0))
+        })
+        .collect()
+}
+
+/// the fixed column of the range table, shared by every chip that looks values up in it
+#[derive(Clone,Debug)]
+pub struct RangeCheckConfig {
+    table: TableColumn,
+}
+
+/// loads the fixed table 0..2^RANGE_BITS, any chip can prove an advice column
+/// is within range by registering a lookup through `lookup`
+pub struct RangeCheckChip<F: FieldExt> {
+    config: RangeCheckConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> RangeCheckChip<F> {
+    /// the chip on the table of config, made by configure
+    pub fn construct(config: RangeCheckConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// add the table column, load_table fills it once per circuit
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+    ) -> RangeCheckConfig {
+        let table = meta.lookup_table_column();
+        RangeCheckConfig { table }
+    }
+
+    /// constrain column * 2^shift to be in 0..2^RANGE_BITS on every row the selector is enabled
+    /// the selector has to be a complex selector
+    pub fn lookup(
+        config: &RangeCheckConfig,
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        selector: Selector,
+        column: Column<Advice>,
+        shift: usize,
+    ) {
+        let table = config.table;
+        meta.lookup(name, |meta| {
+            let s = meta.query_selector(selector);
+            let v = meta.query_advice(column, Rotation::cur());
+            // when the selector is off we look up 0, which is always in the table
+            vec![(s * v * pow_of_two::<F>(shift), table)]
+        });
+    }
+
+    pub fn load_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(|| "range table", |mut table| {
+            for i in 0..(1usize << RANGE_BITS) {
+                table.assign_cell(
+                    || "range",
+                    self.config.table,
+                    i,
+                    || Ok(F::from(i as u64)),
+                )?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// the order the comparators leave a pair in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    // the smaller element first
+    Ascending,
+    Descending,
+}
+
+/// how the field elements a comparator sees stand for integers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    // values in [0, 2^bits)
+    Unsigned,
+    // values in [-2^(bits - 1), 2^(bits - 1)), a negative -x is the field element p - x
+    Signed,
+}
+
+/// the constant that maps the values of encoding onto [0, 2^bits) without changing their order,
+/// the comparator works on the biased values
+pub fn bias<F: FieldExt>(bits: usize, encoding: Encoding) -> F {
+    match encoding {
+        Encoding::Unsigned => F::zero(),
+        Encoding::Signed => pow_of_two::<F>(bits - 1),
+    }
+}
+
+/// a compare-and-swap row for values of a fixed bit-width
+#[derive(Clone,Debug)]
+pub struct CompareConfig {
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    min: Column<Advice>,
+    max: Column<Advice>,
+    // the payloads of RecordChip are swapped by the same cond
+    pub(crate) cond: Column<Advice>,
+    s_comp: Selector,
+    // rhs - lhs + cond * 2^bits split into RANGE_BITS limbs, little endian
+    limbs: Vec<Column<Advice>>,
+    s_limbs: Selector,
+    // range check of a single value put in the lhs column
+    s_range: Selector,
+    // bit-width of the compared values
+    bits: usize,
+    // which of min and max select moves to the lower index
+    pub(crate) order: Order,
+    encoding: Encoding,
+}
+
+/// compares and swaps two values of a fixed bit-width on a single row, and proves single
+/// values to be within the bit-width on the same limb columns
+pub struct CompareChip<F: FieldExt> {
+    config: CompareConfig,
+    _marker: PhantomData<F>,
+}
+
+// the largest bit-width such that rhs - lhs + 2^bits never wraps around the modulus
+pub const MAX_BITS: usize = 252;
+
+impl<F: FieldExt> CompareChip<F>{
+    /// the chip on the columns of config, made by configure
+    pub fn construct(config: CompareConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// configure a comparator for values of bits bits, at most MAX_BITS, whose limbs are looked up
+    /// in the table of rangeconfig, leaving each pair in order and reading the values in encoding
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        rangeconfig: &RangeCheckConfig,
+        bits: usize,
+        order: Order,
+        encoding: Encoding,
+    ) -> CompareConfig {
+        assert!(bits > 0 && bits <= MAX_BITS, "unsupported bit-width {}", bits);
+        // create columns
+        let rhs = meta.advice_column();
+        let lhs = meta.advice_column();
+        let cond = meta.advice_column();
+        let min = meta.advice_column();
+        let max = meta.advice_column();
+        let limbs: Vec<Column<Advice>> = (0..bits.div_ceil(RANGE_BITS))
+            .map(|_| meta.advice_column())
+            .collect();
+        let s_comp = meta.selector();
+        let s_limbs = meta.complex_selector();
+        let s_range = meta.selector();
+
+        meta.enable_equality(rhs);
+        meta.enable_equality(lhs);
+        meta.enable_equality(min);
+        meta.enable_equality(max);
+
+        // every limb is proven to be in 0..2^RANGE_BITS by the range table
+        for limb in limbs.iter() {
+            RangeCheckChip::lookup(rangeconfig, meta, "compare limb", s_limbs, *limb, 0);
+        }
+        // the most significant limb only has bits % RANGE_BITS bits,
+        // shifting it up must still land in the table
+        if bits % RANGE_BITS != 0 {
+            let top = *limbs.last().unwrap();
+            RangeCheckChip::lookup(rangeconfig, meta, "compare top limb", s_limbs, top, RANGE_BITS - bits % RANGE_BITS);
+        }
+
+        // compare and swap in a single row:
+        // cond is boolean, (min, max) is (lhs, rhs) when cond is 0 and (rhs, lhs) when cond is 1,
+        // and rhs - lhs + cond * 2^bits is the value recomposed from the limbs,
+        // it is in [0, 2^bits) only if cond is 1 exactly when lhs > rhs,
+        // the bias cancels out of rhs - lhs, so the gate is the same for both encodings
+        meta.create_gate("compare and swap", |meta| {
+            let s_comp = meta.query_selector(s_comp);
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let rhs = meta.query_advice(rhs, Rotation::cur());
+            let cond = meta.query_advice(cond, Rotation::cur());
+            let min = meta.query_advice(min, Rotation::cur());
+            let max = meta.query_advice(max, Rotation::cur());
+            let acc = Self::recompose(meta, &limbs);
+            let one = Expression::Constant(F::one());
+
+            vec![s_comp.clone() * cond.clone() * (one - cond.clone()),
+                s_comp.clone() * (min - lhs.clone() - cond.clone() * (rhs.clone() - lhs.clone())),
+                s_comp.clone() * (max - rhs.clone() + cond.clone() * (rhs.clone() - lhs.clone())),
+                s_comp * (rhs - lhs + cond * pow_of_two::<F>(bits) - acc)]
+        });
+
+        // lhs + bias is made of the limbs, hence in [0, 2^bits)
+        meta.create_gate("range", |meta| {
+            let s_range = meta.query_selector(s_range);
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let acc = Self::recompose(meta, &limbs);
+
+            vec![s_range * (lhs + bias::<F>(bits, encoding) - acc)]
+        });
+
+        CompareConfig {
+            lhs, rhs, min, max, cond, s_comp,
+            limbs, s_limbs, s_range,
+            bits, order, encoding,
+        }
+    }
+
+
+    /// the value the little endian limbs on the current row stand for
+    fn recompose(
+        meta: &mut VirtualCells<'_, F>,
+        limbs: &[Column<Advice>],
+    ) -> Expression<F> {
+        let mut acc = Expression::Constant(F::zero());
+        for limb in limbs.iter().rev() {
+            acc = acc * pow_of_two::<F>(RANGE_BITS) + meta.query_advice(*limb, Rotation::cur());
+        }
+        acc
+    }
+
+    /// decompose the value of limb into limbsize / RANGE_BITS limbs, little endian,
+    /// and assign them on row offset, each of them is range checked by the lookup
+    pub fn decompose_limb(
+        &self,
+        region: &mut Region<F>,
+        limb: &Limb<F>,
+        limbsize: usize,
+        offset: usize,
+    ) -> Result<Vec<Limb<F>>, Error> {
+        let mut limbs = vec![];
+        let count = limbsize.div_ceil(RANGE_BITS);
+        let values = limb.value.map(|value| limb_values::<F>(&field_to_bn(&value), count));
+        for i in 0..count {
+            let v = values.as_ref().map(|values| values[i]);
+            let cell = region.assign_advice(|| "limb", self.config.limbs[i], offset, || v.ok_or(Error::Synthesis))?;
+            limbs.push(Limb::new(Some(cell), v));
+        }
+        Ok(limbs)
+    }
+
+    /// the bias of the configured bit-width and encoding
+    fn bias(&self) -> F {
+        bias::<F>(self.config.bits, self.config.encoding)
+    }
+
+    /// copy limb into the lhs column on row offset and prove it fits in the configured bit-width
+    pub fn range_check(
+        &self,
+        region: &mut Region<F>,
+        limb: &Limb<F>,
+        offset: usize,
+    ) -> Result<(), Error> {
+        limb.cell.clone().unwrap().copy_advice(|| "range check", region, self.config.lhs, offset)?;
+        let biased = limb.value.map(|value| value + self.bias());
+        self.decompose_limb(region, &Limb::new(None, biased), self.config.bits, offset)?;
+        self.config.s_limbs.enable(region, offset)?;
+        self.config.s_range.enable(region, offset)?;
+        Ok(())
+    }
+
+    /// range check limbs on consecutive rows from offset on, returns the first row after them
+    /// the comparator is only sound for inputs within the bit-width
+    pub fn range_check_all(
+        &self,
+        region: &mut Region<F>,
+        limbs: &[Limb<F>],
+        offset: usize,
+    ) -> Result<usize, Error> {
+        for (k, limb) in limbs.iter().enumerate() {
+            self.range_check(region, limb, offset + k)?;
+        }
+        Ok(offset + limbs.len())
+    }
+
+    /// whether the comparator swaps lhs and rhs, which it does when lhs > rhs in the configured encoding
+    pub fn swaps(&self, lhs: F, rhs: F) -> bool {
+        field_to_bn(&(lhs + self.bias())) > field_to_bn(&(rhs + self.bias()))
+    }
+
+    /// compare lhs with rhs on row offset, returns the min and the max cells
+    /// cond is 1 when lhs > rhs in the configured encoding, in which case the two values are swapped
+    fn compare_and_swap(
+        &self,
+        region: &mut Region<F>,
+        lhs: &Limb<F>,
+        rhs: &Limb<F>,
+        offset: usize,
+    ) -> Result<(Limb<F>, Limb<F>), Error> {
+        let x = pow_of_two::<F>(self.config.bits);
+        // x has form 100000000
+        // if lhs <= rhs, y = rhs - lhs already fits in bits and cond is 0
+        // o.w. y = x - (lhs - rhs) fits and cond is 1, we return (rhs, lhs)
+        let values = lhs.value.zip(rhs.value);
+        let cond = values.map(|(l, r)| if self.swaps(l, r) {F::one()} else {F::zero()});
+        let y = values.zip(cond).map(|((l, r), cond)| r - l + cond * x);
+        self.decompose_limb(region, &Limb::new(None, y), self.config.bits, offset)?;
+        let swapped = values.zip(cond).map(|((l, r), cond)| if cond == F::zero() {(l, r)} else {(r, l)});
+        let (min, max) = (swapped.map(|(min, _)| min), swapped.map(|(_, max)| max));
+        // the inputs are copied from the cells of the previous row
+        lhs.cell.clone().unwrap().copy_advice(|| "lhs", region, self.config.lhs, offset)?;
+        rhs.cell.clone().unwrap().copy_advice(|| "rhs", region, self.config.rhs, offset)?;
+        region.assign_advice(|| "cond", self.config.cond, offset, || cond.ok_or(Error::Synthesis))?;
+        let min_cell = region.assign_advice(|| "min", self.config.min, offset, || min.ok_or(Error::Synthesis))?;
+        let max_cell = region.assign_advice(|| "max", self.config.max, offset, || max.ok_or(Error::Synthesis))?;
+        self.config.s_comp.enable(region, offset)?;
+        self.config.s_limbs.enable(region, offset)?;
+        Ok((Limb::new(Some(min_cell), min), Limb::new(Some(max_cell), max)))
+    }
+
+    /// compare arr[i] with arr[j], i < j, on row offset and replace them with the output cells
+    /// in the configured order
+    pub fn select<const N: usize>(
+        &self,
+        region: &mut Region<F>,
+        arr: &mut [Limb<F>; N],
+        offset: usize,
+        i: usize,
+        j: usize,
+    ) -> Result<(), Error> {
+        let (min, max) = self.compare_and_swap(region, &arr[i], &arr[j], offset)?;
+        // later rows are copied from the freshly assigned outputs,
+        // the order only changes this wiring, the gate is the same
+        let (first, second) = match self.config.order {
+            Order::Ascending => (min, max),
+            Order::Descending => (max, min),
+        };
+        arr[i] = first;
+        arr[j] = second;
+        Ok(())
+    }
+
+    /// prove lhs <= rhs: comparing them must leave them in place
+    pub fn assert_le(
+        &self,
+        region: &mut Region<F>,
+        lhs: &Limb<F>,
+        rhs: &Limb<F>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        let (min, max) = self.compare_and_swap(region, lhs, rhs, *offset)?;
+        *offset += 1;
+        region.constrain_equal(min.cell.unwrap().cell(), lhs.cell.clone().unwrap().cell())?;
+        region.constrain_equal(max.cell.unwrap().cell(), rhs.cell.clone().unwrap().cell())?;
+        Ok(())
+    }
+}
+
+/// comparisons between values of the bit-width the chip is configured for,
+/// every call takes a region of its own
+pub trait CompareInstructions<F: FieldExt>: Chip<F> {
+    type Num;
+
+    /// prove a is within the configured bit-width and encoding,
+    /// the other instructions are only sound for such values
+    fn check_range(&self, layouter: impl Layouter<F>, a: &Self::Num) -> Result<(), Error>;
+
+    /// returns (min(a, b), max(a, b))
+    fn compare(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<(Self::Num, Self::Num), Error>;
+
+    /// prove a <= b
+    fn check_le(&self, layouter: impl Layouter<F>, a: &Self::Num, b: &Self::Num) -> Result<(), Error>;
+}
+
+impl<F: FieldExt> Chip<F> for CompareChip<F> {
+    type Config = CompareConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> CompareInstructions<F> for CompareChip<F> {
+    type Num = Limb<F>;
+
+    fn check_range(&self, mut layouter: impl Layouter<F>, a: &Self::Num) -> Result<(), Error> {
+        layouter.assign_region(|| "range check", |mut region| self.range_check(&mut region, a, 0))
+    }
+
+    fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<(Self::Num, Self::Num), Error> {
+        layouter.assign_region(|| "compare", |mut region| self.compare_and_swap(&mut region, a, b, 0))
+    }
+
+    fn check_le(&self, mut layouter: impl Layouter<F>, a: &Self::Num, b: &Self::Num) -> Result<(), Error> {
+        layouter.assign_region(|| "compare", |mut region| self.assert_le(&mut region, a, b, &mut 0))
+    }
+}
+
+#[cfg(test)]
+mod soundness;
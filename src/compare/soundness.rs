@@ -0,0 +1,248 @@
+//! a malicious prover keeps the constraint system and the copy constraints of
+//! BubSortCircuit, which are fixed by the keys, and only changes the values it assigns,
+//! every such witness for a wrong sort has to be rejected
+
+use halo2_proofs::{
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pairing::bn256::Fr,
+    plonk::*,
+};
+use num_bigint::BigUint;
+use crate::circuit::{minimal_k, BubSortCircuit};
+use crate::compare::{CompareChip, CompareConfig, Encoding, Order, RANGE_BITS};
+use crate::limb::{field_to_bn, pow_of_two, Limb};
+use crate::network::SortingNetwork;
+use crate::prover;
+use crate::sort::{Commitment, MainChip, MainConfig, SortInstructions};
+
+const N: usize = 5;
+const BITS: usize = 8;
+
+/// what the malicious prover does at one comparator of the bubble sort
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Attack {
+    // the honest witness, the harness has to accept it
+    Honest,
+    // cond says swap but min and max are left in place
+    WrongSwap,
+    // cond is flipped to keep an unordered pair in place
+    ForgedCond,
+    // cond is neither 0 nor 1, min and max are chosen to fit
+    NonBooleanCond,
+    // max repeats min, the other element is lost
+    Duplicate,
+    // the row after the comparator holds the sentinel instead of the copied element
+    Drop,
+}
+
+/// the failure a check has to report
+enum Expect {
+    Gate(&'static str),
+    Permutation,
+}
+
+/// the layout of BubSortCircuit with a private input and output, the comparator
+/// number step is assigned by attack
+#[derive(Clone, Copy)]
+struct MaliciousSortCircuit {
+    arr: [Fr; N],
+    step: usize,
+    attack: Attack,
+}
+
+impl Circuit<Fr> for MaliciousSortCircuit {
+    type Config = MainConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        *self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        MainChip::configure(meta, BITS, Order::Ascending, Encoding::Unsigned, Commitment::Disabled)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        let comp_chip = CompareChip::construct(config.compareconfig.clone());
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        let (v, len) = chip.load_array(layouter.namespace(|| "first row"), Some(self.arr), Some(N))?;
+        layouter.constrain_instance(len.cell.unwrap().cell(), config.instance, 0)?;
+
+        layouter.namespace(|| "sort").assign_region(|| "row", |mut region| {
+            let mut v = v.clone();
+            let mut offset = comp_chip.range_check_all(&mut region, &v, 1)?;
+            for (step, (i, j)) in SortingNetwork::Bubble.schedule(N).into_iter().enumerate() {
+                let attack = if step == self.step { self.attack } else { Attack::Honest };
+                match attack {
+                    Attack::Honest | Attack::Drop => comp_chip.select(&mut region, &mut v, offset, i, j)?,
+                    _ => malicious_select(&config.compareconfig, &mut region, &mut v, offset, i, j, attack)?,
+                }
+                offset += 1;
+                v = if attack == Attack::Drop {
+                    malicious_load_row(&config, &mut region, &v, offset, j)?
+                } else {
+                    chip.load_row(&mut region, &v, &mut offset)?
+                };
+                offset += 1;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// assign the cells of a compare-and-swap row the way the honest select does, with forged values
+fn malicious_select(
+    config: &CompareConfig,
+    region: &mut Region<'_, Fr>,
+    arr: &mut [Limb<Fr>; N],
+    offset: usize,
+    i: usize,
+    j: usize,
+    attack: Attack,
+) -> Result<(), Error> {
+    let (lhs, rhs) = (arr[i].value.unwrap(), arr[j].value.unwrap());
+    let swap = field_to_bn(&lhs) > field_to_bn(&rhs);
+    let (cond, min, max) = match attack {
+        Attack::WrongSwap => (Fr::one(), lhs, rhs),
+        Attack::ForgedCond => if swap { (Fr::zero(), lhs, rhs) } else { (Fr::one(), rhs, lhs) },
+        // min = lhs + cond (rhs - lhs) and max = rhs - cond (rhs - lhs) still hold
+        Attack::NonBooleanCond => {
+            let cond = Fr::from(2);
+            (cond, lhs + cond * (rhs - lhs), rhs - cond * (rhs - lhs))
+        }
+        Attack::Duplicate => if swap { (Fr::one(), rhs, rhs) } else { (Fr::zero(), lhs, lhs) },
+        Attack::Honest | Attack::Drop => unreachable!(),
+    };
+
+    arr[i].cell.clone().unwrap().copy_advice(|| "lhs", region, config.lhs, offset)?;
+    arr[j].cell.clone().unwrap().copy_advice(|| "rhs", region, config.rhs, offset)?;
+    region.assign_advice(|| "cond", config.cond, offset, || Ok(cond))?;
+    let min_cell = region.assign_advice(|| "min", config.min, offset, || Ok(min))?;
+    let max_cell = region.assign_advice(|| "max", config.max, offset, || Ok(max))?;
+    // the limbs are the low bits of rhs - lhs + cond * 2^bits, each of them passes the lookup
+    let y = field_to_bn(&(rhs - lhs + cond * pow_of_two::<Fr>(config.bits)));
+    for (k, column) in config.limbs.iter().enumerate() {
+        let limb = (&y >> (k * RANGE_BITS)) & BigUint::from((1u64 << RANGE_BITS) - 1);
+        let limb = Fr::from(limb.to_u64_digits().first().copied().unwrap_or(0));
+        region.assign_advice(|| "limb", *column, offset, || Ok(limb))?;
+    }
+    config.s_comp.enable(region, offset)?;
+    config.s_limbs.enable(region, offset)?;
+
+    arr[i] = Limb::new(Some(min_cell), Some(min));
+    arr[j] = Limb::new(Some(max_cell), Some(max));
+    Ok(())
+}
+
+/// the copy constraints of load_row, with the sentinel assigned in slot dropped
+fn malicious_load_row(
+    config: &MainConfig<N>,
+    region: &mut Region<'_, Fr>,
+    row: &[Limb<Fr>; N],
+    offset: usize,
+    dropped: usize,
+) -> Result<[Limb<Fr>; N], Error> {
+    let mut copied = vec![];
+    for (i, limb) in row.iter().enumerate() {
+        let value = if i == dropped { BubSortCircuit::<Fr, N, BITS>::sentinel() } else { limb.value.unwrap() };
+        let cell = region.assign_advice(|| "copied", config.nums[i], offset, || Ok(value))?;
+        region.constrain_equal(limb.cell.clone().unwrap().cell(), cell.cell())?;
+        copied.push(Limb::new(Some(cell), Some(value)));
+    }
+    Ok(copied.try_into().unwrap())
+}
+
+fn failures<C: Circuit<Fr>>(circuit: &C) -> Vec<VerifyFailure> {
+    let public_inputs = vec![Fr::from(N as u64)];
+    let k = minimal_k(circuit, &[public_inputs.clone()]).unwrap();
+    match MockProver::run(k, circuit, vec![public_inputs]).unwrap().verify() {
+        Ok(()) => vec![],
+        Err(failures) => failures,
+    }
+}
+
+fn assert_rejected<C: Circuit<Fr>>(circuit: &C, expect: Expect) {
+    let failures = failures(circuit);
+    let found = failures.iter().any(|failure| match (&expect, failure) {
+        (Expect::Gate(gate), VerifyFailure::ConstraintNotSatisfied { .. }) => failure.to_string().contains(gate),
+        (Expect::Permutation, VerifyFailure::Permutation { .. }) => true,
+        _ => false,
+    });
+    assert!(found, "expected failure not among {:?}", failures);
+}
+
+// the first comparator sees 100 > 90, the second 100 < 120
+const ARR: [u64; N] = [100, 90, 120, 70, 66];
+
+fn attack(step: usize, attack: Attack) -> MaliciousSortCircuit {
+    MaliciousSortCircuit { arr: ARR.map(Fr::from), step, attack }
+}
+
+#[test]
+fn honest_witness_is_accepted() {
+    assert!(failures(&attack(0, Attack::Honest)).is_empty());
+}
+
+#[test]
+fn attacks_use_the_keys_of_bub_sort_circuit() {
+    // same fixed columns and copy constraints as the real circuit
+    let circuit = BubSortCircuit::<Fr, N, BITS>::padded(&ARR.map(Fr::from));
+    let params = prover::setup(minimal_k(&circuit, &[circuit.public_inputs()]).unwrap());
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    for a in [Attack::WrongSwap, Attack::ForgedCond, Attack::NonBooleanCond, Attack::Duplicate, Attack::Drop] {
+        let malicious = keygen_vk(&params, &attack(0, a)).unwrap();
+        assert_eq!(format!("{:?}", vk), format!("{:?}", malicious), "{:?}", a);
+    }
+}
+
+#[test]
+fn wrong_swap_is_rejected() {
+    assert_rejected(&attack(0, Attack::WrongSwap), Expect::Gate("compare and swap"));
+}
+
+#[test]
+fn forged_cond_is_rejected() {
+    // keeping an unordered pair and swapping an ordered one
+    assert_rejected(&attack(0, Attack::ForgedCond), Expect::Gate("compare and swap"));
+    assert_rejected(&attack(1, Attack::ForgedCond), Expect::Gate("compare and swap"));
+}
+
+#[test]
+fn non_boolean_cond_is_rejected() {
+    assert_rejected(&attack(0, Attack::NonBooleanCond), Expect::Gate("compare and swap"));
+}
+
+#[test]
+fn duplicated_element_is_rejected() {
+    assert_rejected(&attack(0, Attack::Duplicate), Expect::Gate("compare and swap"));
+    assert_rejected(&attack(1, Attack::Duplicate), Expect::Gate("compare and swap"));
+}
+
+#[test]
+fn dropped_element_is_rejected() {
+    assert_rejected(&attack(0, Attack::Drop), Expect::Permutation);
+    assert_rejected(&attack(3, Attack::Drop), Expect::Permutation);
+}
+
+#[test]
+fn out_of_range_value_is_rejected() {
+    // the honest limbs of 2^BITS and of -1 do not add up to the value
+    for value in [Fr::from(1 << BITS), -Fr::one()] {
+        let mut arr = ARR.map(Fr::from);
+        arr[2] = value;
+        assert_rejected(&BubSortCircuit::<Fr, N, BITS>::padded(&arr), Expect::Gate("range"));
+    }
+}
+
+#[test]
+fn out_of_range_signed_value_is_rejected() {
+    // 2^(BITS - 1) and -2^(BITS - 1) - 1 are just outside the signed range
+    for value in [Fr::from(1 << (BITS - 1)), -Fr::from((1 << (BITS - 1)) + 1)] {
+        let mut arr = ARR.map(Fr::from);
+        arr[0] = -arr[0];
+        arr[2] = value;
+        assert_rejected(&BubSortCircuit::<Fr, N, BITS, false, true>::padded(&arr), Expect::Gate("range"));
+    }
+}
@@ -0,0 +1,336 @@
+//! the comparator for arbitrary field elements, such as hashes, through their canonical limbs
+
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+use num_bigint::BigUint;
+use crate::compare::{limb_values, CompareInstructions, RangeCheckChip, RangeCheckConfig, RANGE_BITS};
+use crate::limb::{field_to_bn, pow_of_two, Limb};
+
+/// one row per RANGE_BITS-bit limb of two values, most significant first
+/// limb_lt and limb_gt order the limbs of the row, lt and gt order the limbs read so far:
+/// they only change on the first row where the limbs differ, so on the last row
+/// they order the two values lexicographically
+/// acc_lhs and acc_rhs recompose the limbs read so far
+#[derive(Clone, Debug)]
+pub struct FieldCompareConfig {
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    limb_lt: Column<Advice>,
+    limb_gt: Column<Advice>,
+    // the gap between two differing limbs, minus 1, range checked
+    diff: Column<Advice>,
+    acc_lhs: Column<Advice>,
+    acc_rhs: Column<Advice>,
+    lt: Column<Advice>,
+    gt: Column<Advice>,
+    // min and max of the recomposed values, on the last row
+    min: Column<Advice>,
+    max: Column<Advice>,
+    s_limb: Selector,
+    s_first: Selector,
+    s_next: Selector,
+    s_swap: Selector,
+    // number of limbs of a field element
+    limbs: usize,
+}
+
+/// the cells of a comparison: the limbs of both sides, and the last row
+struct LimbRows<F: FieldExt> {
+    lhs: Vec<Limb<F>>,
+    rhs: Vec<Limb<F>>,
+    acc_lhs: Limb<F>,
+    acc_rhs: Limb<F>,
+    lt: Limb<F>,
+    gt: Limb<F>,
+}
+
+/// compares any two field elements as integers in 0..p, at the cost of one row per
+/// limb and per operand, the CompareChip is far cheaper for values of a known bit-width
+pub struct FieldCompareChip<F: FieldExt> {
+    config: FieldCompareConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FieldCompareChip<F> {
+    pub fn construct(config: FieldCompareConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        rangeconfig: &RangeCheckConfig,
+    ) -> FieldCompareConfig {
+        let lhs = meta.advice_column();
+        let rhs = meta.advice_column();
+        let limb_lt = meta.advice_column();
+        let limb_gt = meta.advice_column();
+        let diff = meta.advice_column();
+        let acc_lhs = meta.advice_column();
+        let acc_rhs = meta.advice_column();
+        let lt = meta.advice_column();
+        let gt = meta.advice_column();
+        let min = meta.advice_column();
+        let max = meta.advice_column();
+        let constant = meta.fixed_column();
+        let s_limb = meta.complex_selector();
+        let s_first = meta.selector();
+        let s_next = meta.selector();
+        let s_swap = meta.selector();
+
+        for column in [lhs, rhs, acc_lhs, acc_rhs, lt, gt, min, max] {
+            meta.enable_equality(column);
+        }
+        // the limbs of the modulus and the expected order come from constants
+        meta.enable_constant(constant);
+
+        for (name, column) in [("lhs limb", lhs), ("rhs limb", rhs), ("limb gap", diff)] {
+            RangeCheckChip::lookup(rangeconfig, meta, name, s_limb, column, 0);
+        }
+
+        // limb_lt and limb_gt are boolean and not both 1, the limbs are equal when both are 0,
+        // and the gap lhs - rhs - 1 or rhs - lhs - 1 is in [0, 2^RANGE_BITS) when one of them is 1
+        meta.create_gate("limb order", |meta| {
+            let s_limb = meta.query_selector(s_limb);
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let rhs = meta.query_advice(rhs, Rotation::cur());
+            let limb_lt = meta.query_advice(limb_lt, Rotation::cur());
+            let limb_gt = meta.query_advice(limb_gt, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let eq = one.clone() - limb_lt.clone() - limb_gt.clone();
+
+            vec![s_limb.clone() * limb_lt.clone() * (one.clone() - limb_lt.clone()),
+                s_limb.clone() * limb_gt.clone() * (one.clone() - limb_gt.clone()),
+                s_limb.clone() * limb_lt.clone() * limb_gt.clone(),
+                s_limb.clone() * eq * (lhs.clone() - rhs.clone()),
+                s_limb * (diff
+                    - limb_gt * (lhs.clone() - rhs.clone() - one.clone())
+                    - limb_lt * (rhs - lhs - one))]
+        });
+
+        meta.create_gate("first limb", |meta| {
+            let s_first = meta.query_selector(s_first);
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let rhs = meta.query_advice(rhs, Rotation::cur());
+            let limb_lt = meta.query_advice(limb_lt, Rotation::cur());
+            let limb_gt = meta.query_advice(limb_gt, Rotation::cur());
+            let acc_lhs = meta.query_advice(acc_lhs, Rotation::cur());
+            let acc_rhs = meta.query_advice(acc_rhs, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let gt = meta.query_advice(gt, Rotation::cur());
+
+            vec![s_first.clone() * (acc_lhs - lhs),
+                s_first.clone() * (acc_rhs - rhs),
+                s_first.clone() * (lt - limb_lt),
+                s_first * (gt - limb_gt)]
+        });
+
+        // the order of the previous rows is kept once decided,
+        // otherwise it is the order of the current limbs
+        meta.create_gate("next limb", |meta| {
+            let s_next = meta.query_selector(s_next);
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let rhs = meta.query_advice(rhs, Rotation::cur());
+            let limb_lt = meta.query_advice(limb_lt, Rotation::cur());
+            let limb_gt = meta.query_advice(limb_gt, Rotation::cur());
+            let acc_lhs_prev = meta.query_advice(acc_lhs, Rotation::prev());
+            let acc_lhs = meta.query_advice(acc_lhs, Rotation::cur());
+            let acc_rhs_prev = meta.query_advice(acc_rhs, Rotation::prev());
+            let acc_rhs = meta.query_advice(acc_rhs, Rotation::cur());
+            let lt_prev = meta.query_advice(lt, Rotation::prev());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let gt_prev = meta.query_advice(gt, Rotation::prev());
+            let gt = meta.query_advice(gt, Rotation::cur());
+            let shift = pow_of_two::<F>(RANGE_BITS);
+            let eq_prev = Expression::Constant(F::one()) - lt_prev.clone() - gt_prev.clone();
+
+            vec![s_next.clone() * (acc_lhs - acc_lhs_prev * shift - lhs),
+                s_next.clone() * (acc_rhs - acc_rhs_prev * shift - rhs),
+                s_next.clone() * (lt - lt_prev - eq_prev.clone() * limb_lt),
+                s_next * (gt - gt_prev - eq_prev * limb_gt)]
+        });
+
+        // (min, max) is (lhs, rhs), swapped when lhs > rhs
+        meta.create_gate("field swap", |meta| {
+            let s_swap = meta.query_selector(s_swap);
+            let lhs = meta.query_advice(acc_lhs, Rotation::cur());
+            let rhs = meta.query_advice(acc_rhs, Rotation::cur());
+            let gt = meta.query_advice(gt, Rotation::cur());
+            let min = meta.query_advice(min, Rotation::cur());
+            let max = meta.query_advice(max, Rotation::cur());
+
+            vec![s_swap.clone() * (min - lhs.clone() - gt.clone() * (rhs.clone() - lhs.clone())),
+                s_swap * (max - rhs.clone() + gt * (rhs - lhs))]
+        });
+
+        FieldCompareConfig {
+            lhs, rhs, limb_lt, limb_gt, diff,
+            acc_lhs, acc_rhs, lt, gt, min, max,
+            s_limb, s_first, s_next, s_swap,
+            limbs: (F::NUM_BITS as usize).div_ceil(RANGE_BITS),
+        }
+    }
+
+    /// the limbs of bn, most significant first
+    fn limbs_of(&self, bn: &BigUint) -> Vec<F> {
+        let mut limbs = limb_values::<F>(bn, self.config.limbs);
+        limbs.reverse();
+        limbs
+    }
+
+    /// lay out one row per limb from row 0 on, a limb with a cell is copied, the others are assigned
+    fn compare_rows(
+        &self,
+        region: &mut Region<F>,
+        lhs: &[Limb<F>],
+        rhs: &[Limb<F>],
+    ) -> Result<LimbRows<F>, Error> {
+        let assign = |region: &mut Region<F>, limb: &Limb<F>, column, offset| -> Result<Limb<F>, Error> {
+            let cell = match &limb.cell {
+                Some(cell) => cell.copy_advice(|| "limb", region, column, offset)?,
+                None => region.assign_advice(|| "limb", column, offset, || limb.value.ok_or(Error::Synthesis))?,
+            };
+            Ok(Limb::new(Some(cell), limb.value))
+        };
+        let shift = pow_of_two::<F>(RANGE_BITS);
+        let mut rows = LimbRows {
+            lhs: vec![],
+            rhs: vec![],
+            acc_lhs: Limb::new(None, Some(F::zero())),
+            acc_rhs: Limb::new(None, Some(F::zero())),
+            lt: Limb::new(None, Some(F::zero())),
+            gt: Limb::new(None, Some(F::zero())),
+        };
+        for (offset, (l, r)) in lhs.iter().zip(rhs.iter()).enumerate() {
+            rows.lhs.push(assign(region, l, self.config.lhs, offset)?);
+            rows.rhs.push(assign(region, r, self.config.rhs, offset)?);
+            let order = l.value.zip(r.value).map(|(l, r)| field_to_bn(&l).cmp(&field_to_bn(&r)));
+            let limb_lt = order.map(|order| if order == Ordering::Less {F::one()} else {F::zero()});
+            let limb_gt = order.map(|order| if order == Ordering::Greater {F::one()} else {F::zero()});
+            let diff = l.value.zip(r.value).zip(order).map(|((l, r), order)| match order {
+                Ordering::Less => r - l - F::one(),
+                Ordering::Equal => F::zero(),
+                Ordering::Greater => l - r - F::one(),
+            });
+            // starting from 0 in acc, lt and gt, the first row follows the same recurrence
+            let acc_lhs = rows.acc_lhs.value.zip(l.value).map(|(acc, l)| acc * shift + l);
+            let acc_rhs = rows.acc_rhs.value.zip(r.value).map(|(acc, r)| acc * shift + r);
+            let eq = rows.lt.value.zip(rows.gt.value).map(|(lt, gt)| F::one() - lt - gt);
+            let lt = rows.lt.value.zip(eq).zip(limb_lt).map(|((lt, eq), limb_lt)| lt + eq * limb_lt);
+            let gt = rows.gt.value.zip(eq).zip(limb_gt).map(|((gt, eq), limb_gt)| gt + eq * limb_gt);
+
+            region.assign_advice(|| "limb lt", self.config.limb_lt, offset, || limb_lt.ok_or(Error::Synthesis))?;
+            region.assign_advice(|| "limb gt", self.config.limb_gt, offset, || limb_gt.ok_or(Error::Synthesis))?;
+            region.assign_advice(|| "limb gap", self.config.diff, offset, || diff.ok_or(Error::Synthesis))?;
+            let acc_lhs_cell = region.assign_advice(|| "acc lhs", self.config.acc_lhs, offset, || acc_lhs.ok_or(Error::Synthesis))?;
+            let acc_rhs_cell = region.assign_advice(|| "acc rhs", self.config.acc_rhs, offset, || acc_rhs.ok_or(Error::Synthesis))?;
+            let lt_cell = region.assign_advice(|| "lt", self.config.lt, offset, || lt.ok_or(Error::Synthesis))?;
+            let gt_cell = region.assign_advice(|| "gt", self.config.gt, offset, || gt.ok_or(Error::Synthesis))?;
+            self.config.s_limb.enable(region, offset)?;
+            if offset == 0 {
+                self.config.s_first.enable(region, offset)?;
+            } else {
+                self.config.s_next.enable(region, offset)?;
+            }
+            rows.acc_lhs = Limb::new(Some(acc_lhs_cell), acc_lhs);
+            rows.acc_rhs = Limb::new(Some(acc_rhs_cell), acc_rhs);
+            rows.lt = Limb::new(Some(lt_cell), lt);
+            rows.gt = Limb::new(Some(gt_cell), gt);
+        }
+        Ok(rows)
+    }
+
+    /// the canonical limbs of x, most significant first: they recompose to x and, read as
+    /// an integer, are below the modulus, so x + p, which may also fit, is ruled out
+    pub fn decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &Limb<F>,
+    ) -> Result<Vec<Limb<F>>, Error> {
+        let limbs: Vec<Limb<F>> = match x.value {
+            Some(value) => self.limbs_of(&field_to_bn(&value)).into_iter().map(|v| Limb::new(None, Some(v))).collect(),
+            None => vec![Limb::new(None, None); self.config.limbs],
+        };
+        let modulus_values = self.limbs_of(&(field_to_bn(&-F::one()) + 1u8));
+        let modulus: Vec<Limb<F>> = modulus_values.iter().map(|v| Limb::new(None, Some(*v))).collect();
+        layouter.assign_region(|| "canonical limbs", |mut region| {
+            let rows = self.compare_rows(&mut region, &limbs, &modulus)?;
+            for (limb, value) in rows.rhs.iter().zip(modulus_values.iter()) {
+                region.constrain_constant(limb.cell.clone().unwrap().cell(), *value)?;
+            }
+            region.constrain_equal(rows.acc_lhs.cell.unwrap().cell(), x.cell.clone().unwrap().cell())?;
+            region.constrain_constant(rows.lt.cell.unwrap().cell(), F::one())?;
+            Ok(rows.lhs)
+        })
+    }
+
+    /// compare the canonical limbs of lhs and rhs, returns (min, max) and the cell holding
+    /// 1 when lhs > rhs, in which case the two values are swapped
+    fn compare_and_swap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lhs: &Limb<F>,
+        rhs: &Limb<F>,
+    ) -> Result<(Limb<F>, Limb<F>, Limb<F>), Error> {
+        let lhs_limbs = self.decompose(layouter.namespace(|| "lhs"), lhs)?;
+        let rhs_limbs = self.decompose(layouter.namespace(|| "rhs"), rhs)?;
+        layouter.assign_region(|| "field compare", |mut region| {
+            // the limbs are copied, so the last row recomposes lhs and rhs
+            let rows = self.compare_rows(&mut region, &lhs_limbs, &rhs_limbs)?;
+            let last = self.config.limbs - 1;
+            let swapped = lhs.value.zip(rhs.value).zip(rows.gt.value)
+                .map(|((l, r), gt)| if gt == F::zero() {(l, r)} else {(r, l)});
+            let (min, max) = (swapped.map(|(min, _)| min), swapped.map(|(_, max)| max));
+            let min_cell = region.assign_advice(|| "min", self.config.min, last, || min.ok_or(Error::Synthesis))?;
+            let max_cell = region.assign_advice(|| "max", self.config.max, last, || max.ok_or(Error::Synthesis))?;
+            self.config.s_swap.enable(&mut region, last)?;
+            Ok((Limb::new(Some(min_cell), min), Limb::new(Some(max_cell), max), rows.gt))
+        })
+    }
+}
+
+impl<F: FieldExt> Chip<F> for FieldCompareChip<F> {
+    type Config = FieldCompareConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> CompareInstructions<F> for FieldCompareChip<F> {
+    type Num = Limb<F>;
+
+    /// every field element is in range, this only proves its canonical limbs exist
+    fn check_range(&self, layouter: impl Layouter<F>, a: &Self::Num) -> Result<(), Error> {
+        self.decompose(layouter, a).map(|_| ())
+    }
+
+    fn compare(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<(Self::Num, Self::Num), Error> {
+        let (min, max, _) = self.compare_and_swap(layouter, a, b)?;
+        Ok((min, max))
+    }
+
+    fn check_le(&self, mut layouter: impl Layouter<F>, a: &Self::Num, b: &Self::Num) -> Result<(), Error> {
+        let (_, _, gt) = self.compare_and_swap(layouter.namespace(|| "compare"), a, b)?;
+        layouter.assign_region(|| "le", |mut region| region.constrain_constant(gt.cell.clone().unwrap().cell(), F::zero()))
+    }
+}
@@ -0,0 +1,24 @@
+//! sorting arrays of small integers in halo2 circuits
+//!
+//! - `limb`: assigned values and the field / integer conversions
+//! - `compare`: the range table and the compare-and-swap chip
+//! - `field_compare`: the comparator for arbitrary field elements
+//! - `sort`: the array rows, padding and exposure of the sort circuits
+//! - `circuit`: `BubSortCircuit`, laid out as a comparator network from `network`
+//! - `record`: `RecordSortCircuit`, the same sort for keys with payload fields
+//!
+//! `permutation` and `transposition` hold the other layouts of the same sort,
+//! `prover` and `serialization` produce and store real proofs
+
+pub mod limb;
+pub mod compare;
+pub mod field_compare;
+pub mod sort;
+pub mod circuit;
+pub mod record;
+pub mod network;
+pub mod permutation;
+pub mod transposition;
+pub mod poseidon_chip;
+pub mod prover;
+pub mod serialization;
@@ -0,0 +1,49 @@
+//! the values the chips pass around, and the conversions between field elements and integers
+
+use halo2_proofs::{
+    arithmetic::{FieldExt, BaseExt},
+    circuit::AssignedCell,
+};
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// the integer a field element stands for, in 0..p
+pub fn field_to_bn<F: BaseExt>(f: &F) -> BigUint {
+    let mut bytes: Vec<u8> = Vec::new();
+    f.write(&mut bytes).unwrap();
+    BigUint::from_bytes_le(&bytes[..])
+}
+
+/// the inverse of field_to_bn, None for integers at or above the modulus
+pub fn bn_to_field<F: BaseExt>(bn: &BigUint) -> Option<F> {
+    let mut bytes = bn.to_bytes_le();
+    if bytes.len() > 32 {
+        return None;
+    }
+    bytes.resize(32, 0);
+    F::read(&mut &bytes[..]).ok()
+}
+
+/// a signed integer as a field element, -x is p - x, None if |x| is at or above the modulus
+pub fn bigint_to_field<F: FieldExt>(bn: &BigInt) -> Option<F> {
+    let magnitude = bn_to_field::<F>(bn.magnitude())?;
+    Some(if bn.sign() == Sign::Minus { -magnitude } else { magnitude })
+}
+
+/// 2^n as a field element
+pub fn pow_of_two<F: FieldExt>(n: usize) -> F {
+    F::from(2).pow_vartime(&[n as u64])
+}
+
+/// an assigned cell along with its value, the value is None while the circuit
+/// is synthesized without witnesses, the cell is None for values not assigned yet
+#[derive(Clone, Debug)]
+pub struct Limb<F: FieldExt> {
+    pub cell: Option<AssignedCell<F, F>>,
+    pub value: Option<F>
+}
+
+impl<F: FieldExt> Limb<F> {
+    pub fn new(cell: Option<AssignedCell<F, F>>, value: Option<F>) -> Self {
+        Limb { cell, value }
+    }
+}
@@ -0,0 +1,109 @@
+//! the comparator schedules the sort circuit can be laid out with
+
+/// comparator networks the sort circuit can be laid out with
+/// every comparator (i, j) has i < j and leaves the smaller element at i,
+/// or the larger one in descending order, which sorts in reverse with the same schedule
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortingNetwork {
+    // n rounds over the n - 1 pairs of neighbours, n(n-1) comparators
+    Bubble,
+    // bubble sort whose round r skips the r largest elements, which are already in place,
+    // n(n-1)/2 comparators
+    ShrinkingBubble,
+    // n phases alternating between even and odd neighbours
+    OddEvenTransposition,
+    // Batcher's odd-even merge sort, O(n log^2 n) comparators
+    OddEvenMerge,
+    // bitonic sort with all comparators ascending, O(n log^2 n) comparators
+    Bitonic,
+}
+
+impl SortingNetwork {
+    /// the comparators sorting n elements, in the order they are applied
+    pub fn schedule(&self, n: usize) -> Vec<(usize, usize)> {
+        match self {
+            SortingNetwork::Bubble => {
+                let mut schedule = vec![];
+                for _round in 0..n {
+                    for idx in 0..n.saturating_sub(1) {
+                        schedule.push((idx, idx + 1));
+                    }
+                }
+                schedule
+            }
+            SortingNetwork::ShrinkingBubble => {
+                let mut schedule = vec![];
+                // after each round the largest remaining element has bubbled to the end
+                for round in 0..n.saturating_sub(1) {
+                    for idx in 0..(n - 1 - round) {
+                        schedule.push((idx, idx + 1));
+                    }
+                }
+                schedule
+            }
+            SortingNetwork::OddEvenTransposition => {
+                let mut schedule = vec![];
+                for phase in 0..n {
+                    let mut idx = phase % 2;
+                    while idx + 1 < n {
+                        schedule.push((idx, idx + 1));
+                        idx += 2;
+                    }
+                }
+                schedule
+            }
+            SortingNetwork::OddEvenMerge => Self::truncate(Self::odd_even_merge(n.next_power_of_two()), n),
+            SortingNetwork::Bitonic => Self::truncate(Self::bitonic(n.next_power_of_two()), n),
+        }
+    }
+
+    /// the networks below are built for a power of two, the missing slots behave like
+    /// elements larger than everything else, which the comparators never move,
+    /// so dropping every comparator that touches them sorts the first n slots
+    fn truncate(schedule: Vec<(usize, usize)>, n: usize) -> Vec<(usize, usize)> {
+        schedule.into_iter().filter(|(_, j)| *j < n).collect()
+    }
+
+    fn odd_even_merge(n: usize) -> Vec<(usize, usize)> {
+        let mut schedule = vec![];
+        let mut p = 1;
+        while p < n {
+            let mut k = p;
+            while k >= 1 {
+                let mut j = k % p;
+                while j + k < n {
+                    for i in 0..k.min(n - j - k) {
+                        if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                            schedule.push((i + j, i + j + k));
+                        }
+                    }
+                    j += 2 * k;
+                }
+                k /= 2;
+            }
+            p *= 2;
+        }
+        schedule
+    }
+
+    fn bitonic(n: usize) -> Vec<(usize, usize)> {
+        let mut schedule = vec![];
+        let mut k = 2;
+        while k <= n {
+            let mut j = k / 2;
+            while j > 0 {
+                for i in 0..n {
+                    // the first step of each merge compares mirrored positions,
+                    // so the blocks never need a descending comparator
+                    let l = if j == k / 2 { i ^ (k - 1) } else { i ^ j };
+                    if l > i {
+                        schedule.push((i, l));
+                    }
+                }
+                j /= 2;
+            }
+            k *= 2;
+        }
+        schedule
+    }
+}
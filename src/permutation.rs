@@ -0,0 +1,217 @@
+//! the sort circuit that checks a supplied sorted array with a permutation argument
+
+use std::marker::PhantomData;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+use crate::circuit::{BubSortCircuit, Visibility};
+use crate::compare::CompareChip;
+use crate::limb::Limb;
+use crate::poseidon_chip::PoseidonChip;
+use crate::sort::{Commitment, MainChip, MainConfig, SortInstructions};
+
+/// grand product over two columns of the same length:
+/// z accumulates prod (r - a_i) and w accumulates prod (r - b_i),
+/// both products agree for a random r only if the b_i are a permutation of the a_i
+#[derive(Clone, Debug)]
+pub struct PermutationConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    r: Column<Advice>,
+    z: Column<Advice>,
+    w: Column<Advice>,
+    s_step: Selector,
+}
+
+/// proves that one row of N cells is a permutation of another under a challenge r
+/// drawn after both rows are fixed, one grand product row per element
+pub struct PermutationChip<F: FieldExt> {
+    config: PermutationConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> PermutationChip<F> {
+    /// the chip on the columns of config, made by configure
+    pub fn construct(config: PermutationConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// configure the columns of the two products and the gate that steps them
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+    ) -> PermutationConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let r = meta.advice_column();
+        let z = meta.advice_column();
+        let w = meta.advice_column();
+        let s_step = meta.selector();
+
+        for column in [a, b, r, z, w] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("grand product", |meta| {
+            let s_step = meta.query_selector(s_step);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let r_cur = meta.query_advice(r, Rotation::cur());
+            let r_next = meta.query_advice(r, Rotation::next());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let w_cur = meta.query_advice(w, Rotation::cur());
+            let w_next = meta.query_advice(w, Rotation::next());
+
+            // the challenge is the same on every row
+            vec![s_step.clone() * (r_next - r_cur.clone()),
+                s_step.clone() * (z_next - z_cur * (r_cur.clone() - a)),
+                s_step * (w_next - w_cur * (r_cur - b))]
+        });
+
+        PermutationConfig { a, b, r, z, w, s_step }
+    }
+
+    /// prove that rhs is a permutation of lhs for the challenge r
+    pub fn assert_permutation(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lhs: &[Limb<F>],
+        rhs: &[Limb<F>],
+        r: &Limb<F>,
+    ) -> Result<(), Error> {
+        assert_eq!(lhs.len(), rhs.len());
+        layouter.assign_region(|| "grand product", |mut region| {
+            let mut z = Some(F::one());
+            let mut w = Some(F::one());
+            let mut z_cell = region.assign_advice_from_constant(|| "z", self.config.z, 0, F::one())?;
+            let mut w_cell = region.assign_advice_from_constant(|| "w", self.config.w, 0, F::one())?;
+            r.cell.clone().unwrap().copy_advice(|| "r", &mut region, self.config.r, 0)?;
+            for (i, (a, b)) in lhs.iter().zip(rhs.iter()).enumerate() {
+                a.cell.clone().unwrap().copy_advice(|| "a", &mut region, self.config.a, i)?;
+                b.cell.clone().unwrap().copy_advice(|| "b", &mut region, self.config.b, i)?;
+                self.config.s_step.enable(&mut region, i)?;
+                z = z.zip(r.value).zip(a.value).map(|((z, r), a)| z * (r - a));
+                w = w.zip(r.value).zip(b.value).map(|((w, r), b)| w * (r - b));
+                region.assign_advice(|| "r", self.config.r, i + 1, || r.value.ok_or(Error::Synthesis))?;
+                z_cell = region.assign_advice(|| "z", self.config.z, i + 1, || z.ok_or(Error::Synthesis))?;
+                w_cell = region.assign_advice(|| "w", self.config.w, i + 1, || w.ok_or(Error::Synthesis))?;
+            }
+            region.constrain_equal(z_cell.cell(), w_cell.cell())
+        })
+    }
+}
+
+/// sorts by checking the prover supplied sorted array instead of running bubble sort:
+/// neighbouring elements of sorted are in order, non-increasing if DESCENDING,
+/// and sorted is a permutation of the input under the challenge poseidon(input, sorted),
+/// which the prover cannot choose freely once both arrays are fixed
+/// the parameters, the fields and the instance column layout are the same as for BubSortCircuit
+#[derive(Debug)]
+pub struct SortCircuit<F, const N: usize, const BITS: usize = 8, const DESCENDING: bool = false, const SIGNED: bool = false> {
+    pub arr: Option<[F; N]>,
+    pub sorted: Option<[F; N]>,
+    pub len: Option<usize>,
+    pub input: Visibility,
+    pub output: Visibility,
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool> Default for SortCircuit<F, N, BITS, DESCENDING, SIGNED> {
+    fn default() -> Self {
+        BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::default().into()
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool> From<BubSortCircuit<F, N, BITS, DESCENDING, SIGNED>> for SortCircuit<F, N, BITS, DESCENDING, SIGNED> {
+    fn from(circuit: BubSortCircuit<F, N, BITS, DESCENDING, SIGNED>) -> Self {
+        Self {
+            arr: circuit.arr,
+            sorted: circuit.sorted(),
+            len: circuit.len,
+            input: circuit.input,
+            output: circuit.output,
+        }
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool> SortCircuit<F, N, BITS, DESCENDING, SIGNED> {
+    /// fill the first values.len() slots with values and pad the rest with the sentinel,
+    /// the sorted array is the witness of the prover
+    pub fn padded(values: &[F]) -> Self {
+        BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::padded(values).into()
+    }
+
+    /// the content of the instance column matching the visibility of input and output
+    pub fn public_inputs(&self) -> Vec<F> {
+        BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED> {
+            arr: self.arr,
+            len: self.len,
+            input: self.input,
+            output: self.output,
+            ..Default::default()
+        }.public_inputs()
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool> Circuit<F> for SortCircuit<F, N, BITS, DESCENDING, SIGNED> {
+    type Config = (MainConfig<N>, PermutationConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // the visibility decides the copy constraints to the instance column
+        Self { input: self.input, output: self.output, ..Self::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let (order, encoding) = (BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::order(), BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::encoding());
+        // the sponge draws the challenge, whatever the visibility
+        (MainChip::configure(meta, BITS, order, encoding, Commitment::Poseidon), PermutationChip::configure(meta))
+    }
+
+    fn synthesize(
+        &self,
+        (config, permutationconfig): Self::Config,
+        mut layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        let comp_chip = CompareChip::construct(config.clone().compareconfig);
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        let poseidon_chip = PoseidonChip::construct(config.clone().poseidonconfig.unwrap());
+        let permutation_chip = PermutationChip::construct(permutationconfig);
+
+        let (input, len) = chip.load_array(
+            layouter.namespace(|| "first row"),
+            self.arr,
+            self.len,
+        )?;
+        let mut instance_row = 0;
+        layouter.constrain_instance(len.cell.unwrap().cell(), config.instance, instance_row)?;
+        instance_row += 1;
+        chip.expose(&mut layouter, &input, self.input, &mut instance_row)?;
+
+        let sorted = chip.load_witness_row(layouter.namespace(|| "sorted row"), self.sorted)?;
+
+        // the challenge binds both arrays
+        let transcript: Vec<Limb<F>> = input.iter().chain(sorted.iter()).cloned().collect();
+        let r = poseidon_chip.hash(layouter.namespace(|| "challenge"), &transcript)?;
+        permutation_chip.assert_permutation(layouter.namespace(|| "permutation"), &input, &sorted, &r)?;
+
+        layouter.assign_region(|| "sortedness", |mut region| {
+            // the sorted elements are in range as a permutation of the input
+            let mut offset = comp_chip.range_check_all(&mut region, &input, 0)?;
+            for pair in sorted.windows(2) {
+                let (lhs, rhs) = if DESCENDING { (&pair[1], &pair[0]) } else { (&pair[0], &pair[1]) };
+                comp_chip.assert_le(&mut region, lhs, rhs, &mut offset)?;
+            }
+            Ok(())
+        })?;
+
+        chip.expose(&mut layouter, &sorted, self.output, &mut instance_row)?;
+        Ok(())
+    }
+}
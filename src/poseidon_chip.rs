@@ -0,0 +1,275 @@
+//! the poseidon sponge the committed arrays are hashed with
+
+use std::marker::PhantomData;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+use poseidon::Spec;
+use crate::limb::Limb;
+
+// full and partial rounds of the width 3 instance over the bn256 scalar field
+pub const R_F: usize = 8;
+pub const R_P: usize = 57;
+
+/// which lanes go through the x^5 sbox in a step of the permutation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Round {
+    // no sbox, only the first round constants are added
+    Add,
+    Full,
+    Partial,
+}
+
+impl Round {
+    fn sbox<F: FieldExt>(&self, lane: usize, x: F) -> F {
+        match self {
+            Round::Full => x.square().square() * x,
+            Round::Partial if lane == 0 => x.square().square() * x,
+            _ => x,
+        }
+    }
+
+    fn sbox_expr<F: FieldExt>(&self, lane: usize, x: Expression<F>) -> Expression<F> {
+        match self {
+            Round::Full => x.clone() * x.clone() * x.clone() * x.clone() * x,
+            Round::Partial if lane == 0 => x.clone() * x.clone() * x.clone() * x.clone() * x,
+            _ => x,
+        }
+    }
+}
+
+/// every step of the optimized permutation has the shape
+/// next = m * (sbox(cur) + rc), where rc and m are loaded in fixed columns,
+/// so the partial rounds can use their own sparse matrices
+#[derive(Clone, Debug)]
+pub struct PoseidonConfig<const T: usize, const RATE: usize> {
+    state: [Column<Advice>; T],
+    // inputs added to lanes 1..=RATE on an absorb row
+    absorb: [Column<Advice>; RATE],
+    rc: [Column<Fixed>; T],
+    m: [[Column<Fixed>; T]; T],
+    constant: Column<Fixed>,
+    s_absorb: Selector,
+    s_add: Selector,
+    s_full: Selector,
+    s_partial: Selector,
+}
+
+/// sponge over the permutation of the poseidon crate with the same padding,
+/// the digest matches poseidon::Poseidon::<F, T, RATE>::new(R_F, R_P)
+pub struct PoseidonChip<F: FieldExt, const T: usize, const RATE: usize> {
+    config: PoseidonConfig<T, RATE>,
+    spec: Spec<F, T, RATE>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE> {
+    pub fn construct(config: PoseidonConfig<T, RATE>) -> Self {
+        Self {
+            config,
+            spec: Spec::new(R_F, R_P),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+    ) -> PoseidonConfig<T, RATE> {
+        let state = [(); T].map(|_| meta.advice_column());
+        let absorb = [(); RATE].map(|_| meta.advice_column());
+        let rc = [(); T].map(|_| meta.fixed_column());
+        let m = [(); T].map(|_| [(); T].map(|_| meta.fixed_column()));
+        let constant = meta.fixed_column();
+        let s_absorb = meta.selector();
+        let s_add = meta.selector();
+        let s_full = meta.selector();
+        let s_partial = meta.selector();
+
+        for column in state.iter().chain(absorb.iter()) {
+            meta.enable_equality(*column);
+        }
+        // initial state and padding come from constants
+        meta.enable_constant(constant);
+
+        meta.create_gate("poseidon absorb", |meta| {
+            let s_absorb = meta.query_selector(s_absorb);
+            let mut constraints = vec![];
+            for (i, column) in state.iter().enumerate() {
+                let cur = meta.query_advice(*column, Rotation::cur());
+                let next = meta.query_advice(*column, Rotation::next());
+                let input = if i == 0 {
+                    Expression::Constant(F::zero())
+                } else {
+                    meta.query_advice(absorb[i - 1], Rotation::cur())
+                };
+                constraints.push(s_absorb.clone() * (next - cur - input));
+            }
+            constraints
+        });
+
+        for (name, selector, round) in [
+            ("poseidon add", s_add, Round::Add),
+            ("poseidon full round", s_full, Round::Full),
+            ("poseidon partial round", s_partial, Round::Partial),
+        ] {
+            meta.create_gate(name, |meta| {
+                let s = meta.query_selector(selector);
+                let cur: Vec<Expression<F>> = state.iter()
+                    .enumerate()
+                    .map(|(j, col)| round.sbox_expr(j, meta.query_advice(*col, Rotation::cur()))
+                        + meta.query_fixed(rc[j], Rotation::cur()))
+                    .collect();
+                let mut constraints = vec![];
+                for (column, row) in state.iter().zip(m.iter()) {
+                    let next = meta.query_advice(*column, Rotation::next());
+                    let mut acc = Expression::Constant(F::zero());
+                    for (m_ij, cur_j) in row.iter().zip(cur.iter()) {
+                        acc = acc + meta.query_fixed(*m_ij, Rotation::cur()) * cur_j.clone();
+                    }
+                    constraints.push(s.clone() * (next - acc));
+                }
+                constraints
+            });
+        }
+
+        PoseidonConfig {
+            state, absorb, rc, m, constant,
+            s_absorb, s_add, s_full, s_partial,
+        }
+    }
+
+    /// the steps of one permutation, mirroring the optimized permutation of the poseidon crate
+    fn schedule(&self) -> Vec<(Round, [F; T], [[F; T]; T])> {
+        let r_f = R_F / 2;
+        let mut identity = [[F::zero(); T]; T];
+        for (i, row) in identity.iter_mut().enumerate() {
+            row[i] = F::one();
+        }
+        let mds = self.spec.mds_matrices().mds().rows();
+        let pre_sparse_mds = self.spec.mds_matrices().pre_sparse_mds().rows();
+        let start = self.spec.constants().start();
+
+        let mut steps = vec![(Round::Add, start[0], identity)];
+        for constants in start.iter().skip(1).take(r_f - 1) {
+            steps.push((Round::Full, *constants, mds));
+        }
+        steps.push((Round::Full, *start.last().unwrap(), pre_sparse_mds));
+
+        let partial = self.spec.constants().partial();
+        let sparse_matrices = self.spec.mds_matrices().sparse_matrices();
+        for (constant, sparse_mds) in partial.iter().zip(sparse_matrices.iter()) {
+            let mut rc = [F::zero(); T];
+            rc[0] = *constant;
+            // row 0 is dense, the other rows are the identity plus the first column
+            let mut m = [[F::zero(); T]; T];
+            m[0] = *sparse_mds.row();
+            for ((i, row), col_hat) in m.iter_mut().enumerate().skip(1).zip(sparse_mds.col_hat().iter()) {
+                row[0] = *col_hat;
+                row[i] = F::one();
+            }
+            steps.push((Round::Partial, rc, m));
+        }
+
+        for constants in self.spec.constants().end().iter() {
+            steps.push((Round::Full, *constants, mds));
+        }
+        steps.push((Round::Full, [F::zero(); T], mds));
+        steps
+    }
+
+    /// hash the values of the cells, the inputs are copy constrained into the absorb rows
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[Limb<F>],
+    ) -> Result<Limb<F>, Error> {
+        let steps = self.schedule();
+        layouter.assign_region(|| "poseidon", |mut region| {
+            let mut offset = 0;
+            let mut initial = [F::zero(); T];
+            initial[0] = F::from_u128(1u128 << 64);
+            let mut cells = vec![];
+            for (column, value) in self.config.state.iter().zip(initial.iter()) {
+                cells.push(region.assign_advice_from_constant(|| "initial state", *column, offset, *value)?);
+            }
+            // unknown as soon as an unknown input is absorbed
+            let mut state = Some(initial);
+
+            // the input is padded with a single one, then zeros up to a multiple of RATE
+            let mut padded: Vec<Option<&Limb<F>>> = inputs.iter().map(Some).collect();
+            padded.push(None);
+            let chunks = padded.len().div_ceil(RATE);
+            for chunk in 0..chunks {
+                for (j, column) in self.config.absorb.iter().enumerate() {
+                    let value = match padded.get(chunk * RATE + j) {
+                        Some(Some(limb)) => {
+                            limb.cell.clone().unwrap().copy_advice(|| "absorb", &mut region, *column, offset)?;
+                            limb.value
+                        }
+                        Some(None) => {
+                            region.assign_advice_from_constant(|| "padding", *column, offset, F::one())?;
+                            Some(F::one())
+                        }
+                        None => {
+                            region.assign_advice_from_constant(|| "padding", *column, offset, F::zero())?;
+                            Some(F::zero())
+                        }
+                    };
+                    state = state.zip(value).map(|(mut state, value)| {
+                        state[j + 1] += value;
+                        state
+                    });
+                }
+                self.config.s_absorb.enable(&mut region, offset)?;
+                offset += 1;
+                cells = self.assign_state(&mut region, &state, offset)?;
+
+                for (round, rc, m) in steps.iter() {
+                    let selector = match round {
+                        Round::Add => self.config.s_add,
+                        Round::Full => self.config.s_full,
+                        Round::Partial => self.config.s_partial,
+                    };
+                    selector.enable(&mut region, offset)?;
+                    for (column, value) in self.config.rc.iter().zip(rc.iter()) {
+                        region.assign_fixed(|| "rc", *column, offset, || Ok(*value))?;
+                    }
+                    for (columns, row) in self.config.m.iter().zip(m.iter()) {
+                        for (column, value) in columns.iter().zip(row.iter()) {
+                            region.assign_fixed(|| "m", *column, offset, || Ok(*value))?;
+                        }
+                    }
+                    state = state.map(|state| {
+                        let mut next = [F::zero(); T];
+                        for (lane, row) in next.iter_mut().zip(m.iter()) {
+                            for (j, m_ij) in row.iter().enumerate() {
+                                *lane += *m_ij * (round.sbox(j, state[j]) + rc[j]);
+                            }
+                        }
+                        next
+                    });
+                    offset += 1;
+                    cells = self.assign_state(&mut region, &state, offset)?;
+                }
+            }
+            Ok(Limb::new(Some(cells[1].clone()), state.map(|state| state[1])))
+        })
+    }
+
+    fn assign_state(
+        &self,
+        region: &mut Region<F>,
+        state: &Option<[F; T]>,
+        offset: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut cells = vec![];
+        for (i, column) in self.config.state.iter().enumerate() {
+            let value = state.map(|state| state[i]);
+            cells.push(region.assign_advice(|| "state", *column, offset, || value.ok_or(Error::Synthesis))?);
+        }
+        Ok(cells)
+    }
+}
@@ -0,0 +1,49 @@
+//! parameters, keys, proofs and verification with KZG on bn256
+
+use halo2_proofs::{
+    pairing::bn256::{Bn256, Fr, G1Affine},
+    plonk::*,
+    poly::commitment::{Params, ParamsVerifier},
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand::rngs::OsRng;
+
+/// KZG parameters for circuits of 2^k rows
+/// the toxic waste is sampled locally, which is only fine for testing
+pub fn setup(k: u32) -> Params<G1Affine> {
+    Params::<G1Affine>::unsafe_setup::<Bn256>(k)
+}
+
+/// generate the verifying and the proving key, the circuit is only used for its shape
+pub fn keygen<C: Circuit<Fr>>(
+    params: &Params<G1Affine>,
+    circuit: &C,
+) -> Result<ProvingKey<G1Affine>, Error> {
+    let vk = keygen_vk(params, circuit)?;
+    keygen_pk(params, vk, circuit)
+}
+
+/// prove circuit against the content of its single instance column, returns the proof bytes
+pub fn prove<C: Circuit<Fr>>(
+    params: &Params<G1Affine>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: &[Fr],
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof(params, pk, &[circuit], &[&[public_inputs]], OsRng, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+/// check proof against the verifying key and the content of the instance column
+pub fn verify(
+    params: &Params<G1Affine>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    public_inputs: &[Fr],
+) -> Result<(), Error> {
+    let params_verifier: ParamsVerifier<Bn256> = params.verifier(public_inputs.len())?;
+    let strategy = SingleVerifier::new(&params_verifier);
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+    verify_proof(&params_verifier, vk, strategy, &[&[public_inputs]], &mut transcript)
+}
@@ -0,0 +1,382 @@
+//! sorting records: a key and P payload fields that move along with it
+
+use std::marker::PhantomData;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+use crate::circuit::{poseidon_digest, BubSortCircuit, Visibility};
+use crate::compare::{bias, Encoding, Order};
+use crate::limb::{field_to_bn, Limb};
+use crate::network::SortingNetwork;
+use crate::sort::{Commitment, MainChip, MainConfig, SortInstructions};
+
+/// a key and the payload fields that are moved with it
+#[derive(Clone, Debug)]
+pub struct Record<F: FieldExt, const P: usize> {
+    pub key: Limb<F>,
+    pub payload: [Limb<F>; P],
+}
+
+/// the rows of MainConfig for the keys, with P payload fields per slot next to them
+#[derive(Clone, Debug)]
+pub struct RecordConfig<const N: usize, const P: usize> {
+    pub main: MainConfig<N>,
+    // field k of the payload of slot i, on the same rows as the keys
+    pub payloads: [[Column<Advice>; N]; P],
+    // the payloads of the compared records, and where they go, on the compare rows
+    lhs: [Column<Advice>; P],
+    rhs: [Column<Advice>; P],
+    min: [Column<Advice>; P],
+    max: [Column<Advice>; P],
+    s_swap: Selector,
+    s_pad: Selector,
+}
+
+/// sorts N records by their keys with the comparators of MainChip,
+/// the payloads are swapped under the cond of the key comparator on the same rows
+pub struct RecordChip<F: FieldExt, const N: usize, const P: usize> {
+    config: RecordConfig<N, P>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const N: usize, const P: usize> RecordChip<F, N, P> {
+    /// the chip on the columns of config, made by configure
+    pub fn construct(config: RecordConfig<N, P>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// configure MainChip for the keys with the arguments of MainChip::configure,
+    /// and the payload columns with the gates that swap and pad them
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        bits: usize,
+        order: Order,
+        encoding: Encoding,
+        commitment: Commitment,
+    ) -> RecordConfig<N, P> {
+        assert!(P > 0, "records need a payload, bare keys are sorted by MainChip");
+        let main = MainChip::configure(meta, bits, order, encoding, commitment);
+        let payloads = [(); P].map(|_| [(); N].map(|_| meta.advice_column()));
+        let lhs = [(); P].map(|_| meta.advice_column());
+        let rhs = [(); P].map(|_| meta.advice_column());
+        let min = [(); P].map(|_| meta.advice_column());
+        let max = [(); P].map(|_| meta.advice_column());
+        let s_swap = meta.selector();
+        let s_pad = meta.selector();
+
+        for column in payloads.iter().flatten().chain(lhs.iter()).chain(rhs.iter()).chain(min.iter()).chain(max.iter()) {
+            meta.enable_equality(*column);
+        }
+
+        // the payloads follow the keys: the cond of the compare row swaps them as well
+        let cond = main.compareconfig.cond;
+        meta.create_gate("payload swap", |meta| {
+            let s_swap = meta.query_selector(s_swap);
+            let cond = meta.query_advice(cond, Rotation::cur());
+            let mut constraints = vec![];
+            for k in 0..P {
+                let lhs = meta.query_advice(lhs[k], Rotation::cur());
+                let rhs = meta.query_advice(rhs[k], Rotation::cur());
+                let min = meta.query_advice(min[k], Rotation::cur());
+                let max = meta.query_advice(max[k], Rotation::cur());
+                constraints.push(s_swap.clone() * (min - lhs.clone() - cond.clone() * (rhs.clone() - lhs.clone())));
+                constraints.push(s_swap.clone() * (max - rhs.clone() + cond.clone() * (rhs - lhs)));
+            }
+            constraints
+        });
+
+        // a padding slot carries an empty payload, its flag is on the row below the keys
+        let nums = main.nums;
+        meta.create_gate("payload padding", |meta| {
+            let s_pad = meta.query_selector(s_pad);
+            let mut constraints = vec![];
+            for i in 0..N {
+                let flag = meta.query_advice(nums[i], Rotation::next());
+                for field in payloads.iter() {
+                    let payload = meta.query_advice(field[i], Rotation::cur());
+                    constraints.push(s_pad.clone() * flag.clone() * payload);
+                }
+            }
+            constraints
+        });
+
+        RecordConfig { main, payloads, lhs, rhs, min, max, s_swap, s_pad }
+    }
+
+    fn main_chip(&self) -> MainChip<F, N> {
+        MainChip::construct(self.config.main.clone())
+    }
+
+    /// load the range table of the key comparator, once per circuit
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.main_chip().load_table(layouter)
+    }
+
+    /// assign the padded records, the slots from len on have to hold the sentinel key
+    /// and a zero payload, returns the records and the length
+    pub fn load_records(
+        &self,
+        mut layouter: impl Layouter<F>,
+        keys: Option<[F; N]>,
+        payloads: Option<[[F; P]; N]>,
+        len: Option<usize>,
+    ) -> Result<([Record<F, P>; N], Limb<F>), Error> {
+        layouter.assign_region(|| "first row", |mut region| {
+            let (keys, len) = self.main_chip().assign_array(&mut region, keys, len)?;
+            let mut records = vec![];
+            for (i, key) in keys.into_iter().enumerate() {
+                let mut payload = vec![];
+                for k in 0..P {
+                    let value = payloads.map(|payloads| payloads[i][k]);
+                    let cell = region.assign_advice(
+                        || format!("payload_{}_{}", i, k),
+                        self.config.payloads[k][i],
+                        0,
+                        || value.ok_or(Error::Synthesis),
+                    )?;
+                    payload.push(Limb::new(Some(cell), value));
+                }
+                records.push(Record { key, payload: payload.try_into().unwrap() });
+            }
+            self.config.s_pad.enable(&mut region, 0)?;
+            Ok((records.try_into().unwrap(), len))
+        })
+    }
+
+    /// copy the payloads of slots i and j onto the compare row offset,
+    /// and move them to the slots select moved their keys to
+    fn swap_payloads(
+        &self,
+        region: &mut Region<F>,
+        payloads: &mut [[Limb<F>; P]; N],
+        offset: usize,
+        i: usize,
+        j: usize,
+        swap: Option<bool>,
+    ) -> Result<(), Error> {
+        let mut min = vec![];
+        let mut max = vec![];
+        for k in 0..P {
+            let (lhs, rhs) = (&payloads[i][k], &payloads[j][k]);
+            lhs.cell.clone().unwrap().copy_advice(|| "lhs payload", region, self.config.lhs[k], offset)?;
+            rhs.cell.clone().unwrap().copy_advice(|| "rhs payload", region, self.config.rhs[k], offset)?;
+            let swapped = lhs.value.zip(rhs.value).zip(swap)
+                .map(|((l, r), swap)| if swap {(r, l)} else {(l, r)});
+            let (min_value, max_value) = (swapped.map(|(min, _)| min), swapped.map(|(_, max)| max));
+            let min_cell = region.assign_advice(|| "min payload", self.config.min[k], offset, || min_value.ok_or(Error::Synthesis))?;
+            let max_cell = region.assign_advice(|| "max payload", self.config.max[k], offset, || max_value.ok_or(Error::Synthesis))?;
+            min.push(Limb::new(Some(min_cell), min_value));
+            max.push(Limb::new(Some(max_cell), max_value));
+        }
+        self.config.s_swap.enable(region, offset)?;
+        let (first, second) = match self.config.main.compareconfig.order {
+            Order::Ascending => (min, max),
+            Order::Descending => (max, min),
+        };
+        payloads[i] = first.try_into().unwrap();
+        payloads[j] = second.try_into().unwrap();
+        Ok(())
+    }
+
+    /// copy the payloads onto row offset, next to the keys
+    fn load_payloads(
+        &self,
+        region: &mut Region<F>,
+        payloads: &[[Limb<F>; P]; N],
+        offset: usize,
+    ) -> Result<[[Limb<F>; P]; N], Error> {
+        let mut copied: Vec<[Limb<F>; P]> = vec![];
+        for (i, payload) in payloads.iter().enumerate() {
+            let mut fields = vec![];
+            for (k, limb) in payload.iter().enumerate() {
+                let cell = limb.cell.clone().unwrap().copy_advice(|| "copied payload", region, self.config.payloads[k][i], offset)?;
+                fields.push(Limb::new(Some(cell), limb.value));
+            }
+            copied.push(fields.try_into().unwrap());
+        }
+        Ok(copied.try_into().unwrap())
+    }
+
+    /// range check the keys and lay out the comparators of network, every compare row
+    /// swaps the payloads under the cond of the keys, returns the sorted records
+    pub fn sort(
+        &self,
+        mut layouter: impl Layouter<F>,
+        records: &[Record<F, P>; N],
+        network: SortingNetwork,
+    ) -> Result<[Record<F, P>; N], Error> {
+        let main_chip = self.main_chip();
+        layouter.assign_region(|| "row", |mut region| {
+            let keys = records.clone().map(|record| record.key);
+            let mut payloads = records.clone().map(|record| record.payload);
+            let keys = main_chip.sort_rows(&mut region, &keys, network, |region, offset, i, j, swap| {
+                self.swap_payloads(region, &mut payloads, offset, i, j, swap)?;
+                // the payloads are copied next to the keys on the row below the compare row
+                payloads = self.load_payloads(region, &payloads, offset + 1)?;
+                Ok(())
+            })?;
+            let sorted: Vec<Record<F, P>> = keys.into_iter().zip(payloads.into_iter())
+                .map(|(key, payload)| Record { key, payload })
+                .collect();
+            Ok(sorted.try_into().unwrap())
+        })
+    }
+
+    /// constrain the records, key first then payload, or their digest,
+    /// to the instance column from instance_row on
+    pub fn expose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        records: &[Record<F, P>; N],
+        visibility: Visibility,
+        instance_row: &mut usize,
+    ) -> Result<(), Error> {
+        let limbs: Vec<Limb<F>> = records.iter()
+            .flat_map(|record| std::iter::once(record.key.clone()).chain(record.payload.iter().cloned()))
+            .collect();
+        self.main_chip().expose_limbs(layouter, &limbs, visibility, instance_row)
+    }
+}
+
+/// BubSortCircuit for records: keys holds the keys, sorted as in BubSortCircuit,
+/// payloads[i] holds the P payload fields moved along with keys[i]
+/// the padding slots hold the sentinel key and a zero payload
+/// the instance column holds len, then the input and output records flattened key first,
+/// or their digests, in the same way as BubSortCircuit
+/// records with equal keys may leave in another order than they came in,
+/// sorted gives the order of the network
+/// COMMITTED configures the poseidon sponge, as for BubSortCircuit
+#[derive(Debug)]
+pub struct RecordSortCircuit<F, const N: usize, const P: usize, const BITS: usize = 8, const DESCENDING: bool = false, const SIGNED: bool = false, const COMMITTED: bool = false> {
+    pub keys: Option<[F; N]>,
+    pub payloads: Option<[[F; P]; N]>,
+    pub len: Option<usize>,
+    pub input: Visibility,
+    pub output: Visibility,
+    pub network: SortingNetwork,
+}
+
+impl<F: FieldExt, const N: usize, const P: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> Default
+    for RecordSortCircuit<F, N, P, BITS, DESCENDING, SIGNED, COMMITTED>
+{
+    fn default() -> Self {
+        Self {
+            keys: None,
+            payloads: None,
+            len: None,
+            input: Visibility::Private,
+            output: Visibility::Private,
+            network: SortingNetwork::Bubble,
+        }
+    }
+}
+
+/// the records key first, then payload, as they appear on the instance column
+fn flatten<F: FieldExt, const N: usize, const P: usize>(keys: &[F; N], payloads: &[[F; P]; N]) -> Vec<F> {
+    keys.iter().zip(payloads.iter())
+        .flat_map(|(key, payload)| std::iter::once(*key).chain(payload.iter().copied()))
+        .collect()
+}
+
+impl<F: FieldExt, const N: usize, const P: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool>
+    RecordSortCircuit<F, N, P, BITS, DESCENDING, SIGNED, COMMITTED>
+{
+    /// fill the first records.len() slots with records and pad the rest
+    pub fn padded(records: &[(F, [F; P])]) -> Self {
+        let keys: Vec<F> = records.iter().map(|(key, _)| *key).collect();
+        let mut payloads = [[F::zero(); P]; N];
+        for (slot, (_, payload)) in payloads.iter_mut().zip(records.iter()) {
+            *slot = *payload;
+        }
+        let circuit = BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::padded(&keys);
+        Self { keys: circuit.arr, payloads: Some(payloads), len: circuit.len, ..Self::default() }
+    }
+
+    /// the padded records in the order the comparators of network leave them
+    pub fn sorted(&self) -> Option<([F; N], [[F; P]; N])> {
+        let encoding = BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::encoding();
+        let bias = bias::<F>(BITS, encoding);
+        self.keys.zip(self.payloads).map(|(mut keys, mut payloads)| {
+            for (i, j) in self.network.schedule(N) {
+                // the comparator swaps when keys[i] > keys[j], descending order exchanges
+                // the outputs, so equal keys change places there
+                let swap = field_to_bn(&(keys[i] + bias)) > field_to_bn(&(keys[j] + bias));
+                if swap != DESCENDING {
+                    keys.swap(i, j);
+                    payloads.swap(i, j);
+                }
+            }
+            (keys, payloads)
+        })
+    }
+
+    /// the content of the instance column matching the visibility of input and output
+    pub fn public_inputs(&self) -> Vec<F> {
+        let keys = self.keys.expect("public inputs need the witness");
+        let payloads = self.payloads.expect("public inputs need the witness");
+        let len = self.len.expect("public inputs need the witness");
+        let (sorted_keys, sorted_payloads) = self.sorted().unwrap();
+        let mut public_inputs = vec![F::from(len as u64)];
+        for (visibility, values) in [
+            (self.input, flatten(&keys, &payloads)),
+            (self.output, flatten(&sorted_keys, &sorted_payloads)),
+        ] {
+            match visibility {
+                Visibility::Public => public_inputs.extend_from_slice(&values),
+                Visibility::Committed => public_inputs.push(poseidon_digest(&values)),
+                Visibility::Private => (),
+            }
+        }
+        public_inputs
+    }
+}
+
+impl<F: FieldExt, const N: usize, const P: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> Circuit<F>
+    for RecordSortCircuit<F, N, P, BITS, DESCENDING, SIGNED, COMMITTED>
+{
+    type Config = RecordConfig<N, P>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // the visibility and the network decide the copy constraints
+        Self { input: self.input, output: self.output, network: self.network, ..Self::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RecordChip::configure(
+            meta,
+            BITS,
+            BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::order(),
+            BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::encoding(),
+            BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED, COMMITTED>::commitment(),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let chip = RecordChip::construct(config.clone());
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        let (records, len) = chip.load_records(
+            layouter.namespace(|| "first row"),
+            self.keys,
+            self.payloads,
+            self.len,
+        )?;
+        let mut instance_row = 0;
+        layouter.constrain_instance(len.cell.unwrap().cell(), config.main.instance, instance_row)?;
+        instance_row += 1;
+        chip.expose(&mut layouter, &records, self.input, &mut instance_row)?;
+        let sorted = chip.sort(layouter.namespace(|| "sort"), &records, self.network)?;
+        chip.expose(&mut layouter, &sorted, self.output, &mut instance_row)?;
+        Ok(())
+    }
+}
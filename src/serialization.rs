@@ -0,0 +1,286 @@
+//! the file formats of keys and proofs
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use halo2_proofs::{
+    pairing::bn256::{Fr, G1Affine},
+    plonk::{Circuit, VerifyingKey},
+    poly::commitment::Params,
+};
+use crate::circuit::BubSortCircuit;
+use crate::permutation::SortCircuit;
+use crate::record::RecordSortCircuit;
+use crate::transposition::TranspositionSortCircuit;
+
+// every file starts with the magic bytes and the format version,
+// bump the version whenever the layout below changes
+const MAGIC: &[u8; 4] = b"SORT";
+const VERSION: u32 = 1;
+
+/// what a file holds, the vk and proof headers are followed by the circuit and its shape
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Params = 0,
+    VerifyingKey = 1,
+    Proof = 2,
+}
+
+/// the sort circuit a key or a proof was written for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitKind {
+    BubSort = 0,
+    TranspositionSort = 1,
+    PermutationSort = 2,
+    RecordSort = 3,
+}
+
+/// the const parameters of a sort circuit, payload is the number of payload fields
+/// of a record and 0 for the circuits that sort bare arrays
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shape {
+    pub n: usize,
+    pub payload: usize,
+    pub bits: usize,
+    pub descending: bool,
+    pub signed: bool,
+    pub committed: bool,
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "N = {}, P = {}, BITS = {}, DESCENDING = {}, SIGNED = {}, COMMITTED = {}",
+            self.n, self.payload, self.bits, self.descending, self.signed, self.committed,
+        )
+    }
+}
+
+/// a circuit whose keys and proofs can be stored, the constraint system is not stored
+/// but rebuilt from the type when reading, so the header records the kind and the shape
+/// of the circuit to refuse files written for another one
+pub trait StoredCircuit: Circuit<Fr> {
+    const KIND: CircuitKind;
+
+    fn shape() -> Shape;
+}
+
+impl<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> StoredCircuit
+    for BubSortCircuit<Fr, N, BITS, DESCENDING, SIGNED, COMMITTED>
+{
+    const KIND: CircuitKind = CircuitKind::BubSort;
+
+    fn shape() -> Shape {
+        Shape { n: N, payload: 0, bits: BITS, descending: DESCENDING, signed: SIGNED, committed: COMMITTED }
+    }
+}
+
+impl<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> StoredCircuit
+    for TranspositionSortCircuit<Fr, N, BITS, DESCENDING, SIGNED, COMMITTED>
+{
+    const KIND: CircuitKind = CircuitKind::TranspositionSort;
+
+    fn shape() -> Shape {
+        Shape { n: N, payload: 0, bits: BITS, descending: DESCENDING, signed: SIGNED, committed: COMMITTED }
+    }
+}
+
+impl<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool> StoredCircuit
+    for SortCircuit<Fr, N, BITS, DESCENDING, SIGNED>
+{
+    const KIND: CircuitKind = CircuitKind::PermutationSort;
+
+    // the sponge that draws the challenge is always configured
+    fn shape() -> Shape {
+        Shape { n: N, payload: 0, bits: BITS, descending: DESCENDING, signed: SIGNED, committed: true }
+    }
+}
+
+impl<const N: usize, const P: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> StoredCircuit
+    for RecordSortCircuit<Fr, N, P, BITS, DESCENDING, SIGNED, COMMITTED>
+{
+    const KIND: CircuitKind = CircuitKind::RecordSort;
+
+    fn shape() -> Shape {
+        Shape { n: N, payload: P, bits: BITS, descending: DESCENDING, signed: SIGNED, committed: COMMITTED }
+    }
+}
+
+/// why a file could not be read back
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    // not a file of this crate
+    Magic,
+    UnsupportedVersion(u32),
+    UnexpectedKind { expected: Kind, found: u8 },
+    // the file was written for another sort circuit
+    UnexpectedCircuit { expected: CircuitKind, found: u8 },
+    // the file was written for a circuit with another array length, payload, bit-width,
+    // order, encoding or without the commitment sponge
+    Shape { expected: Shape, found: Shape },
+    // the length of the proof does not match the bytes left in the file
+    ProofLength { claimed: u64, available: u64 },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Io(e) => write!(f, "{}", e),
+            FormatError::Magic => write!(f, "not a sort circuit file"),
+            FormatError::UnsupportedVersion(v) => write!(f, "unsupported format version {}, expected {}", v, VERSION),
+            FormatError::UnexpectedKind { expected, found } => write!(f, "expected {:?}, found kind {}", expected, found),
+            FormatError::UnexpectedCircuit { expected, found } => write!(f, "expected a {:?} circuit, found circuit kind {}", expected, found),
+            FormatError::Shape { expected, found } => write!(f, "written for {}, but the circuit has {}", found, expected),
+            FormatError::ProofLength { claimed, available } => write!(
+                f,
+                "the proof claims {} bytes but {} bytes are left in the file",
+                claimed, available,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<io::Error> for FormatError {
+    fn from(e: io::Error) -> Self {
+        FormatError::Io(e)
+    }
+}
+
+fn write_header(writer: &mut impl Write, kind: Kind) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&[kind as u8])
+}
+
+fn read_header(reader: &mut impl Read, kind: Kind) -> Result<(), FormatError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(FormatError::Magic);
+    }
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+    let mut found = [0u8; 1];
+    reader.read_exact(&mut found)?;
+    if found[0] != kind as u8 {
+        return Err(FormatError::UnexpectedKind { expected: kind, found: found[0] });
+    }
+    Ok(())
+}
+
+fn write_circuit<C: StoredCircuit>(writer: &mut impl Write) -> io::Result<()> {
+    let shape = C::shape();
+    writer.write_all(&[C::KIND as u8])?;
+    for value in [shape.n, shape.payload, shape.bits] {
+        writer.write_all(&(value as u64).to_le_bytes())?;
+    }
+    writer.write_all(&[shape.descending as u8, shape.signed as u8, shape.committed as u8])
+}
+
+fn read_circuit<C: StoredCircuit>(reader: &mut impl Read) -> Result<(), FormatError> {
+    let mut kind = [0u8; 1];
+    reader.read_exact(&mut kind)?;
+    if kind[0] != C::KIND as u8 {
+        return Err(FormatError::UnexpectedCircuit { expected: C::KIND, found: kind[0] });
+    }
+    let mut sizes = [0usize; 3];
+    for size in sizes.iter_mut() {
+        let mut buf = [0u8; 8];
+        reader.read_exact(&mut buf)?;
+        *size = u64::from_le_bytes(buf) as usize;
+    }
+    let mut flags = [0u8; 3];
+    reader.read_exact(&mut flags)?;
+    let found = Shape {
+        n: sizes[0],
+        payload: sizes[1],
+        bits: sizes[2],
+        descending: flags[0] != 0,
+        signed: flags[1] != 0,
+        committed: flags[2] != 0,
+    };
+    if found != C::shape() {
+        return Err(FormatError::Shape { expected: C::shape(), found });
+    }
+    Ok(())
+}
+
+pub fn write_params(path: impl AsRef<Path>, params: &Params<G1Affine>) -> Result<(), FormatError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer, Kind::Params)?;
+    params.write(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_params(path: impl AsRef<Path>) -> Result<Params<G1Affine>, FormatError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_header(&mut reader, Kind::Params)?;
+    Ok(Params::read(&mut reader)?)
+}
+
+/// the vk of C, read back by read_vk::<C> only
+pub fn write_vk<C: StoredCircuit>(
+    path: impl AsRef<Path>,
+    vk: &VerifyingKey<G1Affine>,
+) -> Result<(), FormatError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer, Kind::VerifyingKey)?;
+    write_circuit::<C>(&mut writer)?;
+    vk.write(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_vk<C: StoredCircuit>(
+    path: impl AsRef<Path>,
+    params: &Params<G1Affine>,
+) -> Result<VerifyingKey<G1Affine>, FormatError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_header(&mut reader, Kind::VerifyingKey)?;
+    read_circuit::<C>(&mut reader)?;
+    Ok(VerifyingKey::read::<_, C>(&mut reader, params)?)
+}
+
+pub fn write_proof<C: StoredCircuit>(
+    path: impl AsRef<Path>,
+    proof: &[u8],
+) -> Result<(), FormatError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer, Kind::Proof)?;
+    write_circuit::<C>(&mut writer)?;
+    writer.write_all(&(proof.len() as u64).to_le_bytes())?;
+    writer.write_all(proof)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// the length prefix is checked against the size of the file before anything is allocated
+pub fn read_proof<C: StoredCircuit>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<u8>, FormatError> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    read_header(&mut reader, Kind::Proof)?;
+    read_circuit::<C>(&mut reader)?;
+    let mut len = [0u8; 8];
+    reader.read_exact(&mut len)?;
+    let claimed = u64::from_le_bytes(len);
+    let available = size.saturating_sub(reader.stream_position()?);
+    if claimed != available {
+        return Err(FormatError::ProofLength { claimed, available });
+    }
+    let mut proof = vec![0u8; claimed as usize];
+    reader.read_exact(&mut proof)?;
+    Ok(proof)
+}
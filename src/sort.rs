@@ -0,0 +1,351 @@
+//! the array rows and the padding of the sort circuits
+
+use std::marker::PhantomData;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+use crate::circuit::Visibility;
+use crate::compare::{bias, CompareChip, CompareConfig, Encoding, Order, RangeCheckChip, RangeCheckConfig};
+use crate::limb::{pow_of_two, Limb};
+use crate::network::SortingNetwork;
+use crate::poseidon_chip::{PoseidonChip, PoseidonConfig};
+
+// width and rate of the sponge used to commit to arrays
+pub const POSEIDON_T: usize = 3;
+pub const POSEIDON_RATE: usize = 2;
+
+/// the rows of an array of N elements, with the chips the sort circuits share
+#[derive(Clone,Debug)]
+pub struct MainConfig<const N: usize>{
+    pub nums: [Column<Advice>; N],
+    // number of real elements, the rest of the N slots hold the sentinel
+    pub len: Column<Advice>,
+    s_pad: Selector,
+    pub instance: Column<Instance>,
+
+    pub compareconfig: CompareConfig,
+    pub rangeconfig: RangeCheckConfig,
+    // the sponge is only configured for circuits that commit to an array
+    pub poseidonconfig: Option<PoseidonConfig<POSEIDON_T, POSEIDON_RATE>>,
+}
+
+/// whether the arrays of a sort circuit can be exposed by their poseidon digest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Commitment {
+    // only public and private arrays, the sponge is left out
+    Disabled,
+    // the sponge is configured next to the rows and committed arrays are hashed with it
+    Poseidon,
+}
+
+/// the value of the unused slots, the largest value of the encoding in ascending order
+/// and the smallest in descending order, it is never moved before a real element
+pub fn sentinel<F: FieldExt>(bits: usize, order: Order, encoding: Encoding) -> F {
+    match order {
+        Order::Ascending => pow_of_two::<F>(bits) - F::one() - bias::<F>(bits, encoding),
+        Order::Descending => -bias::<F>(bits, encoding),
+    }
+}
+
+/// the rows of an array of N elements and the comparators sorting them,
+/// the sort circuits lay out one region per network with the chip and expose its arrays
+pub struct MainChip<F: FieldExt, const N: usize>{
+    config: MainConfig<N>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const N: usize> MainChip<F, N> {
+    /// the chip on the columns of config, made by configure
+    pub fn construct(config: MainConfig<N>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// configure the rows of N elements and the padding gate, with the range table
+    /// and a comparator for values of bits bits, at most MAX_BITS
+    /// order is the order the comparators leave a pair in, the sentinel of the padding follows it,
+    /// encoding decides whether the elements are unsigned or signed integers,
+    /// commitment adds the poseidon sponge that exposing a committed array needs
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        bits: usize,
+        order: Order,
+        encoding: Encoding,
+        commitment: Commitment,
+    ) -> MainConfig<N> {
+        let rangeconfig = RangeCheckChip::configure(meta);
+        let compareconfig = CompareChip::configure(meta, &rangeconfig, bits, order, encoding);
+        let poseidonconfig = match commitment {
+            Commitment::Disabled => None,
+            Commitment::Poseidon => Some(PoseidonChip::<F, POSEIDON_T, POSEIDON_RATE>::configure(meta)),
+        };
+        // create columns, one per element of the array
+        let nums = [(); N].map(|_| meta.advice_column());
+
+        // enable permutation checks for the following columns
+        // we use copy_advice to perform such checks
+        for num in nums.iter() {
+            meta.enable_equality(*num);
+        }
+        let len = meta.advice_column();
+        let instance = meta.instance_column();
+        let s_pad = meta.selector();
+        meta.enable_equality(len);
+        meta.enable_equality(instance);
+
+        // the first row holds the padded input, the row below holds a flag p_i per slot
+        // p_i is boolean, a flagged slot holds the sentinel, which sorts to the end:
+        // the largest value of the encoding in ascending order and the smallest in descending order,
+        // flags never go back to 0 and len counts the unflagged slots,
+        // so exactly the slots from len on are padding
+        meta.create_gate("padding", |meta| {
+            let s_pad = meta.query_selector(s_pad);
+            let len = meta.query_advice(len, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let sentinel = Expression::Constant(sentinel::<F>(bits, order, encoding));
+            let flags: Vec<Expression<F>> = nums.iter()
+                .map(|num| meta.query_advice(*num, Rotation::next()))
+                .collect();
+
+            let mut constraints = vec![];
+            let mut count = Expression::Constant(F::zero());
+            for (i, num) in nums.iter().enumerate() {
+                let x = meta.query_advice(*num, Rotation::cur());
+                let p = flags[i].clone();
+                constraints.push(s_pad.clone() * p.clone() * (one.clone() - p.clone()));
+                constraints.push(s_pad.clone() * p.clone() * (x - sentinel.clone()));
+                if i + 1 < N {
+                    constraints.push(s_pad.clone() * p.clone() * (one.clone() - flags[i + 1].clone()));
+                }
+                count = count + one.clone() - p;
+            }
+            constraints.push(s_pad * (len - count));
+            constraints
+        });
+
+        MainConfig {
+            nums,
+            len,
+            s_pad,
+            instance,
+            compareconfig,
+            rangeconfig,
+            poseidonconfig,
+        }
+    }
+
+    /// copy row onto row offset
+    pub fn load_row(
+        &self, 
+        region: &mut Region<F>,
+        row: &[Limb<F>; N],
+        offset: &mut usize,
+    ) -> Result<[Limb<F>; N], Error> {
+        // use copy advice to do permutation checks
+        let mut copied = vec![];
+        for (i, limb) in row.iter().enumerate() {
+            let cell = limb.cell.clone().unwrap().copy_advice(||"copied", region,self.config.nums[i],*offset,)?;
+            copied.push(Limb::new(Some(cell), limb.value));
+        }
+        Ok(copied.try_into().unwrap())
+    }
+
+    /// range check arr from row 1 of region on and lay out the comparators of network below,
+    /// each on a compare row followed by the row of the next copy of the array,
+    /// returns the sorted array
+    /// step is called after every compare with the compare row, the compared slots
+    /// and whether they swap, so that other columns can follow the array
+    pub fn sort_rows(
+        &self,
+        region: &mut Region<F>,
+        arr: &[Limb<F>; N],
+        network: SortingNetwork,
+        mut step: impl FnMut(&mut Region<F>, usize, usize, usize, Option<bool>) -> Result<(), Error>,
+    ) -> Result<[Limb<F>; N], Error> {
+        let comp_chip = CompareChip::construct(self.config.compareconfig.clone());
+        // the floor planner may assign the region more than once,
+        // every call has to start from the cells of the first row
+        let mut v = arr.clone();
+        let mut offset = comp_chip.range_check_all(region, &v, 1)?;
+        for (i, j) in network.schedule(N) {
+            let swap = v[i].value.zip(v[j].value).map(|(l, r)| comp_chip.swaps(l, r));
+            comp_chip.select(region, &mut v, offset, i, j)?;
+            step(region, offset, i, j, swap)?;
+            offset += 1;
+            // the next compare reads the row just written
+            v = self.load_row(region, &v, &mut offset)?;
+            offset += 1;
+        }
+        Ok(v)
+    }
+
+    /// assign the padded array on row 0 of region and its padding flags on row 1,
+    /// returns the array and the length
+    pub fn assign_array(
+        &self,
+        region: &mut Region<F>,
+        arr: Option<[F; N]>,
+        len: Option<usize>,
+    ) -> Result<([Limb<F>; N], Limb<F>), Error> {
+        let mut row = vec![];
+        for i in 0..N {
+            let value = arr.map(|arr| arr[i]);
+            let cell = region.assign_advice(
+                ||format!("num_{}", i),
+                self.config.nums[i],
+                0,
+                || value.ok_or(Error::Synthesis),
+            )?;
+            row.push(Limb::new(Some(cell), value));
+            let flag = len.map(|len| if i < len {F::zero()} else {F::one()});
+            region.assign_advice(
+                ||format!("pad_{}", i),
+                self.config.nums[i],
+                1,
+                || flag.ok_or(Error::Synthesis),
+            )?;
+        }
+        let len = len.map(|len| F::from(len as u64));
+        let len_cell = region.assign_advice(|| "len", self.config.len, 0, || len.ok_or(Error::Synthesis))?;
+        self.config.s_pad.enable(region, 0)?;
+        Ok((row.try_into().unwrap(), Limb::new(Some(len_cell), len)))
+    }
+
+    /// constrain limbs, or their digest, to the instance column from instance_row on,
+    /// committed limbs need a config with Commitment::Poseidon
+    pub fn expose_limbs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        limbs: &[Limb<F>],
+        visibility: Visibility,
+        instance_row: &mut usize,
+    ) -> Result<(), Error> {
+        match visibility {
+            Visibility::Public => {
+                for limb in limbs.iter() {
+                    layouter.constrain_instance(limb.cell.clone().unwrap().cell(), self.config.instance, *instance_row)?;
+                    *instance_row += 1;
+                }
+            }
+            Visibility::Committed => {
+                let poseidonconfig = self.config.poseidonconfig.clone().ok_or(Error::Synthesis)?;
+                let poseidon_chip = PoseidonChip::<F, POSEIDON_T, POSEIDON_RATE>::construct(poseidonconfig);
+                let digest = poseidon_chip.hash(layouter.namespace(|| "commitment"), limbs)?;
+                layouter.constrain_instance(digest.cell.unwrap().cell(), self.config.instance, *instance_row)?;
+                *instance_row += 1;
+            }
+            Visibility::Private => (),
+        }
+        Ok(())
+    }
+
+    /// assign arr as a plain row, without padding flags
+    pub fn load_witness_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        arr: Option<[F; N]>,
+    ) -> Result<[Limb<F>; N], Error> {
+        layouter.assign_region(|| "witness row", |mut region| {
+            let mut row = vec![];
+            for i in 0..N {
+                let value = arr.map(|arr| arr[i]);
+                let cell = region.assign_advice(|| format!("num_{}", i), self.config.nums[i], 0, || value.ok_or(Error::Synthesis))?;
+                row.push(Limb::new(Some(cell), value));
+            }
+            Ok(row.try_into().unwrap())
+        })
+    }
+}
+
+/// what a circuit needs to sort an array of N elements and expose it,
+/// the chip keeps the padded array in N advice columns
+pub trait SortInstructions<F: FieldExt, const N: usize>: Chip<F> {
+    type Num;
+
+    /// load the lookup tables the chip relies on, once per circuit
+    fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error>;
+
+    /// assign the padded array, the slots from len on have to hold the sentinel,
+    /// returns the array and the length
+    fn load_array(
+        &self,
+        layouter: impl Layouter<F>,
+        arr: Option<[F; N]>,
+        len: Option<usize>,
+    ) -> Result<([Self::Num; N], Self::Num), Error>;
+
+    /// range check arr and lay out the comparators of network, returns the sorted array
+    /// the region of MainChip has an empty first row, N rows of range checks and two rows per comparator
+    fn sort(
+        &self,
+        layouter: impl Layouter<F>,
+        arr: &[Self::Num; N],
+        network: SortingNetwork,
+    ) -> Result<[Self::Num; N], Error>;
+
+    /// constrain the array, or its digest, to the instance column from instance_row on,
+    /// a committed array needs a config with Commitment::Poseidon
+    fn expose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        arr: &[Self::Num; N],
+        visibility: Visibility,
+        instance_row: &mut usize,
+    ) -> Result<(), Error>;
+}
+
+impl<F: FieldExt, const N: usize> Chip<F> for MainChip<F, N> {
+    type Config = MainConfig<N>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const N: usize> SortInstructions<F, N> for MainChip<F, N> {
+    type Num = Limb<F>;
+
+    fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        RangeCheckChip::construct(self.config.rangeconfig.clone()).load_table(layouter)
+    }
+
+    fn load_array(
+        &self,
+        mut layouter: impl Layouter<F>,
+        arr: Option<[F; N]>,
+        len: Option<usize>,
+    ) -> Result<([Self::Num; N], Self::Num), Error> {
+        layouter.assign_region(||"first row", |mut region| self.assign_array(&mut region, arr, len))
+    }
+
+    fn sort(
+        &self,
+        mut layouter: impl Layouter<F>,
+        arr: &[Self::Num; N],
+        network: SortingNetwork,
+    ) -> Result<[Self::Num; N], Error> {
+        layouter.assign_region(|| "row", |mut region| {
+            self.sort_rows(&mut region, arr, network, |_, _, _, _, _| Ok(()))
+        },)
+    }
+
+    fn expose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        arr: &[Self::Num; N],
+        visibility: Visibility,
+        instance_row: &mut usize,
+    ) -> Result<(), Error> {
+        self.expose_limbs(layouter, arr, visibility, instance_row)
+    }
+}
@@ -0,0 +1,116 @@
+//! the sort circuit laid out as odd-even transposition rounds of comparators side by side
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+};
+use crate::circuit::{BubSortCircuit, Visibility};
+use crate::compare::{CompareChip, CompareConfig};
+use crate::sort::{MainChip, MainConfig, SortInstructions};
+
+/// odd-even transposition sort with every comparison of a phase on the same row:
+/// N / 2 comparator column groups work side by side, so the N phases take N rows
+/// and the outputs of a phase are read directly by the next one
+/// the parameters, the fields and the instance column layout are the same as for BubSortCircuit
+#[derive(Debug)]
+pub struct TranspositionSortCircuit<F, const N: usize, const BITS: usize = 8, const DESCENDING: bool = false, const SIGNED: bool = false, const COMMITTED: bool = false> {
+    pub arr: Option<[F; N]>,
+    pub len: Option<usize>,
+    pub input: Visibility,
+    pub output: Visibility,
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> Default for TranspositionSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    fn default() -> Self {
+        BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED, COMMITTED>::default().into()
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> From<BubSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED>> for TranspositionSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    fn from(circuit: BubSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED>) -> Self {
+        Self {
+            arr: circuit.arr,
+            len: circuit.len,
+            input: circuit.input,
+            output: circuit.output,
+        }
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> TranspositionSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    /// fill the first values.len() slots with values and pad the rest with the sentinel
+    pub fn padded(values: &[F]) -> Self {
+        BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED, COMMITTED>::padded(values).into()
+    }
+
+    /// the content of the instance column matching the visibility of input and output
+    pub fn public_inputs(&self) -> Vec<F> {
+        BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+            arr: self.arr,
+            len: self.len,
+            input: self.input,
+            output: self.output,
+            ..Default::default()
+        }.public_inputs()
+    }
+}
+
+impl<F: FieldExt, const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool, const COMMITTED: bool> Circuit<F> for TranspositionSortCircuit<F, N, BITS, DESCENDING, SIGNED, COMMITTED> {
+    // the comparator of the main config is the first group
+    type Config = (MainConfig<N>, Vec<CompareConfig>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // the visibility decides the copy constraints to the instance column
+        Self { input: self.input, output: self.output, ..Self::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let (order, encoding) = (BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::order(), BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED>::encoding());
+        let commitment = BubSortCircuit::<F, N, BITS, DESCENDING, SIGNED, COMMITTED>::commitment();
+        let config = MainChip::configure(meta, BITS, order, encoding, commitment);
+        let mut groups = vec![config.compareconfig.clone()];
+        for _ in 1..(N / 2) {
+            groups.push(CompareChip::configure(meta, &config.rangeconfig, BITS, order, encoding));
+        }
+        (config, groups)
+    }
+
+    fn synthesize(
+        &self,
+        (config, groups): Self::Config,
+        mut layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        let comp_chips: Vec<CompareChip<F>> = groups.into_iter().map(CompareChip::construct).collect();
+        chip.load_table(layouter.namespace(|| "range table"))?;
+
+        let (v, len) = chip.load_array(
+            layouter.namespace(|| "first row"),
+            self.arr,
+            self.len,
+        )?;
+        let mut instance_row = 0;
+        layouter.constrain_instance(len.cell.unwrap().cell(), config.instance, instance_row)?;
+        instance_row += 1;
+        chip.expose(&mut layouter, &v, self.input, &mut instance_row)?;
+
+        let sorted = layouter.assign_region(|| "phases", |mut region| {
+            let mut v = v.clone();
+            let mut offset = comp_chips[0].range_check_all(&mut region, &v, 0)?;
+            // the pairs of a phase are disjoint, the g-th pair goes to the g-th group
+            for phase in 0..N {
+                let pairs = (phase % 2..N.saturating_sub(1)).step_by(2);
+                for (g, i) in pairs.enumerate() {
+                    comp_chips[g].select(&mut region, &mut v, offset, i, i + 1)?;
+                }
+                offset += 1;
+            }
+            Ok(v)
+        })?;
+
+        chip.expose(&mut layouter, &sorted, self.output, &mut instance_row)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+//! BubSortCircuit against the mock prover: bit-widths, lengths, visibilities and
+//! comparator networks
+
+mod common;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    pairing::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use halo2_bubble_sort::circuit::{minimal_k, poseidon_digest, BubSortCircuit, Visibility};
+use halo2_bubble_sort::compare::RANGE_BITS;
+use halo2_bubble_sort::limb::{field_to_bn, pow_of_two};
+use halo2_bubble_sort::network::SortingNetwork;
+use common::{mock_prover, values};
+
+#[test]
+fn sorts_five_elements() {
+    let circuit = BubSortCircuit::<Fr, 5>::padded(&values());
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn range_table_keeps_the_sort_small() {
+    // a few rows per compare-and-swap, the 2^8 rows of the range table dominate
+    let circuit = BubSortCircuit::<Fr, 5>::padded(&values());
+    let prover = MockProver::run(9, &circuit, vec![circuit.public_inputs()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+/// the largest value below 2^bits
+fn top(bits: usize) -> Fr {
+    pow_of_two::<Fr>(bits) - Fr::one()
+}
+
+#[test]
+fn sorts_at_wider_bit_widths() {
+    // elements close to the top of the range
+    let circuit = BubSortCircuit::<Fr, 5, 16>::padded(&[top(16), Fr::from(90), top(16) - Fr::from(3), Fr::zero(), Fr::from(66)]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = BubSortCircuit::<Fr, 5, 32>::padded(&[top(32), Fr::from(90), top(32) - Fr::from(3), Fr::zero(), top(16)]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = BubSortCircuit::<Fr, 5, 64>::padded(&[top(64), Fr::from(90), top(64) - Fr::from(3), Fr::zero(), top(32)]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = BubSortCircuit::<Fr, 5, 128>::padded(&[top(128), Fr::from(90), top(128) - Fr::from(3), Fr::zero(), top(64)]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = BubSortCircuit::<Fr, 5, 250>::padded(&[top(250), Fr::from(90), top(250) - Fr::from(3), Fr::zero(), top(128)]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn element_above_the_width_is_rejected() {
+    let [_, b, c, d, e] = values();
+    let circuit = BubSortCircuit::<Fr, 5, 8>::padded(&[Fr::from(256), b, c, d, e]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert!(prover.verify().is_err());
+
+    // 2^BITS itself, at the widths whose limbs are full
+    let circuit = BubSortCircuit::<Fr, 5, 32>::padded(&[pow_of_two(32), Fr::from(90), Fr::zero()]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert!(prover.verify().is_err());
+
+    let circuit = BubSortCircuit::<Fr, 5, 128>::padded(&[pow_of_two(128), Fr::from(90), Fr::zero()]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn equal_elements_keep_cond_at_zero() {
+    let [a, b, _, _, e] = values();
+    let circuit = BubSortCircuit::<Fr, 5>::padded(&[b, e, b, a, e]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn every_round_reads_the_previous_one() {
+    // an already sorted array and the reversed one, whose largest element
+    // travels through every compare-and-swap of the first round
+    let [a, b, c, d, e] = values();
+    for arr in [[e, d, c, b, a], [a, e, d, c, b]] {
+        let circuit = BubSortCircuit::<Fr, 5>::padded(&arr);
+        let prover = mock_prover(&circuit, circuit.public_inputs());
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[test]
+fn other_array_lengths() {
+    let circuit = BubSortCircuit::<Fr, 2>::padded(&[Fr::from(7), Fr::from(3)]);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = BubSortCircuit::<Fr, 16>{
+        arr: Some([255u64, 0, 17, 17, 3, 200, 42, 1, 99, 128, 64, 5, 250, 8, 17, 77].map(Fr::from)),
+        len: Some(16),
+        ..Default::default()
+    };
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut arr = [Fr::zero(); 100];
+    for (i, x) in arr.iter_mut().enumerate() {
+        *x = Fr::from((i as u64 * 37) % 256);
+    }
+    let circuit = BubSortCircuit::<Fr, 100>{ arr: Some(arr), len: Some(100), ..Default::default() };
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn one_shape_for_every_length_up_to_the_capacity() {
+    for values in [vec![], vec![Fr::from(9)], vec![Fr::from(200), Fr::from(255), Fr::from(3)]] {
+        let circuit = BubSortCircuit::<Fr, 8>::padded(&values);
+        let prover = mock_prover(&circuit, vec![Fr::from(values.len() as u64)]);
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[test]
+fn wrong_length_is_rejected() {
+    // the padding shows 3 elements
+    let circuit = BubSortCircuit::<Fr, 8>::padded(&[Fr::from(200), Fr::from(255), Fr::from(3)]);
+    let prover = mock_prover(&circuit, vec![Fr::from(4)]);
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn public_and_private_arrays() {
+    // a verifier checks that the public output is the sort of the public input
+    let mut circuit = BubSortCircuit::<Fr, 8>::padded(&values());
+    circuit.input = Visibility::Public;
+    circuit.output = Visibility::Public;
+    let public_inputs = circuit.public_inputs();
+    let prover = mock_prover(&circuit, public_inputs.clone());
+    assert_eq!(prover.verify(), Ok(()));
+
+    // an output that is not the sort of the input is rejected
+    let mut modified_public_inputs = public_inputs;
+    modified_public_inputs.swap(1 + 8, 1 + 8 + 1);
+    let prover = mock_prover(&circuit, modified_public_inputs);
+    assert!(prover.verify().is_err());
+
+    // or only learns the sorted output of a private input
+    circuit.input = Visibility::Private;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn committed_arrays() {
+    let [a, b, c, d, e] = values();
+    // a private input committed to by its poseidon digest, with the sorted array public
+    let mut circuit = BubSortCircuit::<Fr, 8, 8, false, false, true>::padded(&values());
+    circuit.input = Visibility::Committed;
+    circuit.output = Visibility::Public;
+    let public_inputs = circuit.public_inputs();
+    assert_eq!(public_inputs[1], poseidon_digest(&circuit.arr.unwrap()));
+    let prover = mock_prover(&circuit, public_inputs);
+    assert_eq!(prover.verify(), Ok(()));
+
+    // or with both sides committed
+    circuit.output = Visibility::Committed;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    // a digest of a different array is rejected
+    let mut modified_public_inputs = circuit.public_inputs();
+    modified_public_inputs[1] = poseidon_digest(&[a, b, c, d, d, e, e, e]);
+    let prover = mock_prover(&circuit, modified_public_inputs);
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn committing_needs_the_sponge() {
+    let mut circuit = BubSortCircuit::<Fr, 5>::padded(&values());
+    circuit.input = Visibility::Committed;
+    assert!(matches!(MockProver::run(18, &circuit, vec![circuit.public_inputs()]), Err(Error::Synthesis)));
+}
+
+#[test]
+fn every_network_sorts() {
+    let [a, b, c, d, e] = values();
+    for network in [
+        SortingNetwork::Bubble,
+        SortingNetwork::ShrinkingBubble,
+        SortingNetwork::OddEvenTransposition,
+        SortingNetwork::OddEvenMerge,
+        SortingNetwork::Bitonic,
+    ] {
+        for values in [vec![a, b, c, d, e], vec![e, Fr::from(255), a, d, a, c, Fr::zero(), b, d, Fr::one(), c]] {
+            let mut circuit = BubSortCircuit::<Fr, 11>::padded(&values);
+            circuit.output = Visibility::Public;
+            circuit.network = network;
+            let prover = mock_prover(&circuit, circuit.public_inputs());
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn descending_order() {
+    // the descending mode puts the largest element first and pads with zeros
+    let [a, b, _, d, e] = values();
+    for network in [SortingNetwork::Bubble, SortingNetwork::OddEvenMerge, SortingNetwork::Bitonic] {
+        let mut circuit = BubSortCircuit::<Fr, 11, 8, true>::padded(&[e, Fr::from(255), a, d, a, Fr::zero(), b]);
+        circuit.output = Visibility::Public;
+        circuit.network = network;
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(public_inputs[1..4], [Fr::from(255), a, a]);
+        let prover = mock_prover(&circuit, public_inputs.clone());
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the ascending order of the same array is rejected
+        let mut ascending = public_inputs;
+        ascending[1..].reverse();
+        let prover = mock_prover(&circuit, ascending);
+        assert!(prover.verify().is_err());
+    }
+}
+
+#[test]
+fn signed_encoding() {
+    // the signed mode sorts negative values, p - x, before the positive ones
+    // and pads with 127 in ascending order and -128 in descending order
+    let [a, b, _, _, e] = values();
+    let mixed = [e, -Fr::from(128), -a, Fr::from(127), Fr::zero(), -Fr::one(), b];
+    for network in [SortingNetwork::Bubble, SortingNetwork::OddEvenMerge, SortingNetwork::Bitonic] {
+        let mut circuit = BubSortCircuit::<Fr, 11, 8, false, true>::padded(&mixed);
+        circuit.output = Visibility::Public;
+        circuit.network = network;
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(public_inputs[1..8], [-Fr::from(128), -a, -Fr::one(), Fr::zero(), e, b, Fr::from(127)]);
+        let prover = mock_prover(&circuit, public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mut circuit = BubSortCircuit::<Fr, 11, 8, true, true>::padded(&mixed);
+        circuit.output = Visibility::Public;
+        circuit.network = network;
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(public_inputs[1..5], [Fr::from(127), b, e, Fr::zero()]);
+        assert_eq!(public_inputs[8..], [-Fr::from(128); 4]);
+        let prover = mock_prover(&circuit, public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    // the unsigned order of the same array is rejected
+    let mut circuit = BubSortCircuit::<Fr, 11, 8, false, true>::padded(&mixed);
+    circuit.output = Visibility::Public;
+    let mut unsigned = circuit.public_inputs();
+    unsigned[1..8].sort_by_key(field_to_bn);
+    let prover = mock_prover(&circuit, unsigned);
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn signed_values_outside_the_width_are_rejected() {
+    // 128 and -129 do not fit in 8 signed bits
+    let [a, b, ..] = values();
+    for value in [Fr::from(128), -Fr::from(129)] {
+        let circuit = BubSortCircuit::<Fr, 11, 8, false, true>::padded(&[a, value, -b]);
+        let prover = mock_prover(&circuit, circuit.public_inputs());
+        assert!(prover.verify().is_err());
+    }
+}
+
+#[test]
+fn minimal_k_grows_with_the_array() {
+    let small = BubSortCircuit::<Fr, 5>::padded(&values());
+    let k_small = minimal_k(&small, &[small.public_inputs()]).unwrap();
+    // the range table sets the floor
+    assert_eq!(k_small, RANGE_BITS as u32 + 1);
+
+    let mut arr = [Fr::zero(); 100];
+    for (i, x) in arr.iter_mut().enumerate() {
+        *x = Fr::from((i as u64 * 37) % 256);
+    }
+    let large = BubSortCircuit::<Fr, 100>::padded(&arr);
+    let k_large = minimal_k(&large, &[large.public_inputs()]).unwrap();
+    assert!(k_large > k_small);
+    // one row less and the sort no longer fits
+    assert!(MockProver::run(k_large - 1, &large, vec![large.public_inputs()]).is_err());
+}
+
+/// fails to synthesize whatever the number of rows
+struct FailingCircuit;
+
+impl Circuit<Fr> for FailingCircuit {
+    type Config = ();
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        FailingCircuit
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<Fr>) -> Self::Config {}
+
+    fn synthesize(&self, _config: Self::Config, _layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        Err(Error::Synthesis)
+    }
+}
+
+#[test]
+fn minimal_k_returns_errors_other_than_too_few_rows() {
+    assert!(matches!(minimal_k(&FailingCircuit, &[]), Err(Error::Synthesis)));
+}
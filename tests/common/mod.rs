@@ -0,0 +1,17 @@
+//! helpers shared by the integration tests, every test crate compiles its own copy
+//! and only uses part of it
+#![allow(dead_code)]
+
+use halo2_proofs::{dev::MockProver, pairing::bn256::Fr, plonk::Circuit};
+use halo2_bubble_sort::circuit::minimal_k;
+
+/// run the mock prover at the minimal k for the circuit
+pub fn mock_prover<C: Circuit<Fr>>(circuit: &C, public_inputs: Vec<Fr>) -> MockProver<Fr> {
+    let k = minimal_k(circuit, &[public_inputs.clone()]).unwrap();
+    MockProver::run(k, circuit, vec![public_inputs]).unwrap()
+}
+
+/// the array of the original circuit
+pub fn values() -> [Fr; 5] {
+    [100u64, 90, 80, 70, 66].map(Fr::from)
+}
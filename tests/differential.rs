@@ -0,0 +1,178 @@
+//! random arrays of every length up to N, with more or fewer duplicates, sorted by
+//! BubSortCircuit with its output public, against the sort of the standard library,
+//! reversed for the descending mode, on signed integers for the signed mode
+//! a failing array is shrunk before it is reported
+//! SORT_SEED replays a run, SORT_CASES overrides the number of arrays per test
+
+use halo2_proofs::{dev::MockProver, pairing::bn256::Fr};
+use num_bigint::{BigInt, RandBigInt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use halo2_bubble_sort::circuit::{minimal_k, BubSortCircuit, Visibility};
+use halo2_bubble_sort::limb::bigint_to_field;
+use halo2_bubble_sort::network::SortingNetwork;
+
+const NETWORKS: [SortingNetwork; 5] = [
+    SortingNetwork::Bubble,
+    SortingNetwork::ShrinkingBubble,
+    SortingNetwork::OddEvenTransposition,
+    SortingNetwork::OddEvenMerge,
+    SortingNetwork::Bitonic,
+];
+
+fn env_or(name: &str, default: u64) -> u64 {
+    std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// the smallest and the largest value of the encoding
+fn bounds<const BITS: usize, const SIGNED: bool>() -> (BigInt, BigInt) {
+    let bottom = if SIGNED { -(BigInt::from(1u8) << (BITS - 1)) } else { BigInt::from(0u8) };
+    let top = &bottom + (BigInt::from(1u8) << BITS) - 1u8;
+    (bottom, top)
+}
+
+/// the instance column the circuit must accept: the length, then the padded input
+/// sorted by slice::sort
+fn expected<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool>(values: &[BigInt]) -> Vec<Fr> {
+    let (bottom, top) = bounds::<BITS, SIGNED>();
+    let sentinel = if DESCENDING { bottom } else { top };
+    let mut sorted = values.to_vec();
+    sorted.resize(N, sentinel);
+    sorted.sort();
+    if DESCENDING {
+        sorted.reverse();
+    }
+    let mut public_inputs = vec![Fr::from(values.len() as u64)];
+    public_inputs.extend(sorted.iter().map(|x| bigint_to_field::<Fr>(x).unwrap()));
+    public_inputs
+}
+
+fn circuit<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool>(values: &[BigInt], network: SortingNetwork) -> BubSortCircuit<Fr, N, BITS, DESCENDING, SIGNED> {
+    let values: Vec<Fr> = values.iter().map(|x| bigint_to_field(x).unwrap()).collect();
+    let mut circuit = BubSortCircuit::<Fr, N, BITS, DESCENDING, SIGNED>::padded(&values);
+    circuit.output = Visibility::Public;
+    circuit.network = network;
+    circuit
+}
+
+fn holds<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool>(k: u32, values: &[BigInt], network: SortingNetwork) -> bool {
+    let circuit = circuit::<N, BITS, DESCENDING, SIGNED>(values, network);
+    let prover = MockProver::run(k, &circuit, vec![expected::<N, BITS, DESCENDING, SIGNED>(values)]).unwrap();
+    prover.verify().is_ok()
+}
+
+/// up to N values of the encoding, drawn from a pool whose size sets the density of duplicates
+fn random_values<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool>(rng: &mut StdRng) -> Vec<BigInt> {
+    let len = rng.gen_range(0..=N);
+    let (bottom, top) = bounds::<BITS, SIGNED>();
+    let pool_size = match rng.gen_range(0..4) {
+        0 => 1,
+        1 => 2,
+        2 => (len / 2).max(1),
+        _ => len.max(1),
+    };
+    let pool: Vec<BigInt> = (0..pool_size)
+        .map(|_| match rng.gen_range(0..8) {
+            // the edges of the range, they are also the sentinels
+            0 => bottom.clone(),
+            1 => top.clone(),
+            _ => rng.gen_bigint_range(&bottom, &(&top + 1u8)),
+        })
+        .collect();
+    (0..len).map(|_| pool[rng.gen_range(0..pool_size)].clone()).collect()
+}
+
+/// drop elements and move them towards 0 as long as the check still fails
+fn shrink<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool>(k: u32, mut values: Vec<BigInt>, network: SortingNetwork) -> Vec<BigInt> {
+    loop {
+        let mut candidates = vec![];
+        for i in 0..values.len() {
+            let mut fewer = values.clone();
+            fewer.remove(i);
+            candidates.push(fewer);
+            if values[i] != BigInt::from(0u8) {
+                for smaller in [BigInt::from(0u8), &values[i] / 2] {
+                    let mut smaller_values = values.clone();
+                    smaller_values[i] = smaller;
+                    candidates.push(smaller_values);
+                }
+            }
+        }
+        match candidates.into_iter().find(|candidate| !holds::<N, BITS, DESCENDING, SIGNED>(k, candidate, network)) {
+            Some(smaller) => values = smaller,
+            None => return values,
+        }
+    }
+}
+
+fn differential<const N: usize, const BITS: usize, const DESCENDING: bool, const SIGNED: bool>(default_cases: u64) {
+    let seed = env_or("SORT_SEED", rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    // one k for every array and network, from the largest layout
+    let k = NETWORKS.iter()
+        .map(|network| {
+            let circuit = circuit::<N, BITS, DESCENDING, SIGNED>(&[], *network);
+            minimal_k(&circuit, &[circuit.public_inputs()]).unwrap()
+        })
+        .max()
+        .unwrap();
+    for _ in 0..env_or("SORT_CASES", default_cases) {
+        let values = random_values::<N, BITS, DESCENDING, SIGNED>(&mut rng);
+        let network = NETWORKS[rng.gen_range(0..NETWORKS.len())];
+        if !holds::<N, BITS, DESCENDING, SIGNED>(k, &values, network) {
+            let shrunk = shrink::<N, BITS, DESCENDING, SIGNED>(k, values.clone(), network);
+            panic!(
+                "N = {}, BITS = {}, DESCENDING = {}, SIGNED = {}, {:?}: the circuit disagrees with slice::sort on {:?}, shrunk from {:?} (SORT_SEED={})",
+                N, BITS, DESCENDING, SIGNED, network, shrunk, values, seed,
+            );
+        }
+    }
+}
+
+#[test]
+fn single_element() {
+    differential::<1, 8, false, false>(200);
+}
+
+#[test]
+fn pairs() {
+    differential::<2, 8, false, false>(1000);
+}
+
+#[test]
+fn bytes() {
+    differential::<8, 8, false, false>(1000);
+}
+
+#[test]
+fn odd_length() {
+    differential::<13, 8, false, false>(300);
+}
+
+#[test]
+fn sixteen_bits() {
+    differential::<8, 16, false, false>(500);
+}
+
+#[test]
+fn sixty_four_bits() {
+    differential::<6, 64, false, false>(300);
+}
+
+#[test]
+fn wide() {
+    differential::<5, 250, false, false>(200);
+}
+
+#[test]
+fn descending() {
+    differential::<8, 8, true, false>(500);
+    differential::<6, 64, true, false>(200);
+}
+
+#[test]
+fn signed() {
+    differential::<8, 8, false, true>(500);
+    differential::<6, 64, false, true>(200);
+    differential::<6, 64, true, true>(200);
+    differential::<5, 250, false, true>(100);
+}
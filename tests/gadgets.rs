@@ -0,0 +1,153 @@
+//! circuits of their own that embed the gadgets through SortInstructions and
+//! CompareInstructions, and the full-field comparator
+
+mod common;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    pairing::bn256::Fr,
+    plonk::*,
+};
+use halo2_bubble_sort::circuit::poseidon_digest;
+use halo2_bubble_sort::compare::{CompareChip, CompareInstructions, Encoding, Order};
+use halo2_bubble_sort::field_compare::{FieldCompareChip, FieldCompareConfig};
+use halo2_bubble_sort::limb::field_to_bn;
+use halo2_bubble_sort::network::SortingNetwork;
+use halo2_bubble_sort::sort::{Commitment, MainChip, MainConfig, SortInstructions};
+use common::{mock_prover, values};
+
+/// embeds the sort gadget: only the smallest and the largest element of a private array
+/// of N elements below 2^8 are public, on rows 1 and 2 after the length
+struct ExtremaCircuit<F, const N: usize> {
+    arr: Option<[F; N]>,
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for ExtremaCircuit<F, N> {
+    type Config = MainConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { arr: None }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MainChip::configure(meta, 8, Order::Ascending, Encoding::Unsigned, Commitment::Disabled)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        let (arr, len) = chip.load_array(layouter.namespace(|| "array"), self.arr, Some(N))?;
+        let sorted = chip.sort(layouter.namespace(|| "sort"), &arr, SortingNetwork::OddEvenMerge)?;
+        for (row, limb) in [len, sorted[0].clone(), sorted[N - 1].clone()].iter().enumerate() {
+            layouter.constrain_instance(limb.cell.clone().unwrap().cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// embeds the compare gadget: a private pair is exposed in order, after its length 2
+struct PairCircuit<F> {
+    pair: Option<[F; 2]>,
+}
+
+impl<F: FieldExt> Circuit<F> for PairCircuit<F> {
+    type Config = MainConfig<2>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { pair: None }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MainChip::configure(meta, 8, Order::Ascending, Encoding::Unsigned, Commitment::Disabled)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        let comp_chip = CompareChip::construct(config.compareconfig.clone());
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        let ([a, b], len) = chip.load_array(layouter.namespace(|| "pair"), self.pair, Some(2))?;
+        comp_chip.check_range(layouter.namespace(|| "a"), &a)?;
+        comp_chip.check_range(layouter.namespace(|| "b"), &b)?;
+        let (min, max) = comp_chip.compare(layouter.namespace(|| "compare"), &a, &b)?;
+        comp_chip.check_le(layouter.namespace(|| "ordered"), &min, &max)?;
+        for (row, limb) in [len, min, max].iter().enumerate() {
+            layouter.constrain_instance(limb.cell.clone().unwrap().cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// embeds the full-field comparator: a private pair of arbitrary field elements
+/// is exposed in order, after its length 2
+struct FieldPairCircuit<F> {
+    pair: Option<[F; 2]>,
+}
+
+impl<F: FieldExt> Circuit<F> for FieldPairCircuit<F> {
+    type Config = (MainConfig<2>, FieldCompareConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { pair: None }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let config = MainChip::configure(meta, 8, Order::Ascending, Encoding::Unsigned, Commitment::Disabled);
+        let fieldconfig = FieldCompareChip::configure(meta, &config.rangeconfig);
+        (config, fieldconfig)
+    }
+
+    fn synthesize(&self, (config, fieldconfig): Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = MainChip::construct(config.clone());
+        let field_chip = FieldCompareChip::construct(fieldconfig);
+        chip.load_table(layouter.namespace(|| "range table"))?;
+        let ([a, b], len) = chip.load_array(layouter.namespace(|| "pair"), self.pair, Some(2))?;
+        let (min, max) = field_chip.compare(layouter.namespace(|| "compare"), &a, &b)?;
+        field_chip.check_le(layouter.namespace(|| "ordered"), &min, &max)?;
+        for (row, limb) in [len, min, max].iter().enumerate() {
+            layouter.constrain_instance(limb.cell.clone().unwrap().cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn extrema() {
+    let [a, b, c, _, e] = values();
+    let circuit = ExtremaCircuit::<Fr, 6> { arr: Some([a, e, Fr::from(255), c, Fr::zero(), b]) };
+    let prover = mock_prover(&circuit, vec![Fr::from(6), Fr::zero(), Fr::from(255)]);
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = mock_prover(&circuit, vec![Fr::from(6), Fr::zero(), a]);
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn pair() {
+    let [a, _, _, _, e] = values();
+    let circuit = PairCircuit::<Fr> { pair: Some([a, e]) };
+    let prover = mock_prover(&circuit, vec![Fr::from(2), e, a]);
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = mock_prover(&circuit, vec![Fr::from(2), a, e]);
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn field_pair() {
+    // the full-field comparator orders hashes and the largest element p - 1 as integers in 0..p
+    let [a, b, ..] = values();
+    let hash = poseidon_digest(&[a, b]);
+    let other = poseidon_digest(&[b, a]);
+    for (x, y) in [(hash, a), (other, hash), (-Fr::one(), Fr::zero()), (hash, -Fr::one()), (hash, hash)] {
+        let (min, max) = if field_to_bn(&x) > field_to_bn(&y) { (y, x) } else { (x, y) };
+        let circuit = FieldPairCircuit::<Fr> { pair: Some([x, y]) };
+        let prover = mock_prover(&circuit, vec![Fr::from(2), min, max]);
+        assert_eq!(prover.verify(), Ok(()));
+        if min != max {
+            let prover = mock_prover(&circuit, vec![Fr::from(2), max, min]);
+            assert!(prover.verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! the permutation argument and the transposition layouts of the same sort,
+//! in every order and encoding
+
+mod common;
+
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_bubble_sort::circuit::Visibility;
+use halo2_bubble_sort::permutation::SortCircuit;
+use halo2_bubble_sort::transposition::TranspositionSortCircuit;
+use common::{mock_prover, values};
+
+/// duplicates, zero and both ends of the signed range of 8 bits
+fn mixed() -> Vec<Fr> {
+    vec![Fr::from(100), -Fr::from(128), Fr::zero(), Fr::from(127), -Fr::one(), Fr::from(100), -Fr::from(66)]
+}
+
+#[test]
+fn permutation_checks_a_supplied_sorted_array() {
+    // in O(n) rows
+    let mut circuit = SortCircuit::<Fr, 8>::padded(&values());
+    circuit.input = Visibility::Public;
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    // a sorted array that is not a permutation of the input is rejected
+    let mut forged = circuit.sorted.unwrap();
+    forged[0] = forged[1];
+    circuit.sorted = Some(forged);
+    circuit.output = Visibility::Private;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert!(prover.verify().is_err());
+
+    // and so is a permutation that is not sorted
+    let mut unsorted = SortCircuit::<Fr, 8>::padded(&values());
+    unsorted.sorted.as_mut().unwrap().swap(0, 1);
+    let prover = mock_prover(&unsorted, unsorted.public_inputs());
+    assert!(prover.verify().is_err());
+
+    let mut arr = [Fr::zero(); 100];
+    for (i, x) in arr.iter_mut().enumerate() {
+        *x = Fr::from((i as u64 * 37) % 256);
+    }
+    let circuit = SortCircuit::<Fr, 100>::padded(&arr);
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn transposition_sorts_in_every_mode() {
+    let mut values = values().to_vec();
+    values.extend([Fr::from(255), Fr::zero()]);
+    let mut circuit = TranspositionSortCircuit::<Fr, 8, 8, true>::padded(&values);
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut circuit = TranspositionSortCircuit::<Fr, 8, 8, false, true>::padded(&mixed());
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut circuit = TranspositionSortCircuit::<Fr, 8, 8, true, true>::padded(&mixed());
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn permutation_sorts_in_every_mode() {
+    let mut values = values().to_vec();
+    values.extend([Fr::from(255), Fr::zero()]);
+    let mut circuit = SortCircuit::<Fr, 8, 8, true>::padded(&values);
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut circuit = SortCircuit::<Fr, 8, 8, false, true>::padded(&mixed());
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut circuit = SortCircuit::<Fr, 8, 8, true, true>::padded(&mixed());
+    circuit.output = Visibility::Public;
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn permutation_rejects_the_other_order() {
+    // an ascending array is not sorted for the descending circuit
+    let [a, b, c, ..] = values();
+    let mut circuit = SortCircuit::<Fr, 8, 8, true>::padded(&[a, b, c]);
+    circuit.sorted.as_mut().unwrap().reverse();
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn transposition_sorts() {
+    // the same network as SortingNetwork::OddEvenTransposition with all comparisons of a phase on one row
+    let [a, b, c, d, e] = values();
+    for values in [vec![a, b, c, d, e], vec![e, Fr::from(255), a, d, a, c, Fr::zero(), b, d, Fr::one(), c]] {
+        let mut circuit = TranspositionSortCircuit::<Fr, 11>::padded(&values);
+        circuit.output = Visibility::Public;
+        let prover = mock_prover(&circuit, circuit.public_inputs());
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,38 @@
+//! the comparator schedules, independent of any circuit
+
+use halo2_bubble_sort::network::SortingNetwork;
+
+const NETWORKS: [SortingNetwork; 5] = [
+    SortingNetwork::Bubble,
+    SortingNetwork::ShrinkingBubble,
+    SortingNetwork::OddEvenTransposition,
+    SortingNetwork::OddEvenMerge,
+    SortingNetwork::Bitonic,
+];
+
+#[test]
+fn bubble_comparator_counts() {
+    for n in 0..=32 {
+        assert_eq!(SortingNetwork::Bubble.schedule(n).len(), n * n.saturating_sub(1));
+        assert_eq!(SortingNetwork::ShrinkingBubble.schedule(n).len(), n * n.saturating_sub(1) / 2);
+    }
+}
+
+#[test]
+fn every_network_sorts_every_zero_one_input() {
+    // a comparator network sorts every input if it sorts every input of zeros and ones
+    for network in NETWORKS {
+        for n in 0..=10 {
+            for bits in 0u32..(1 << n) {
+                let mut v: Vec<u32> = (0..n).map(|i| (bits >> i) & 1).collect();
+                for (i, j) in network.schedule(n) {
+                    assert!(i < j && j < n, "{:?}: comparator ({}, {}) for {} elements", network, i, j, n);
+                    if v[i] > v[j] {
+                        v.swap(i, j);
+                    }
+                }
+                assert!(v.windows(2).all(|w| w[0] <= w[1]), "{:?} does not sort {:b} of {} elements", network, bits, n);
+            }
+        }
+    }
+}
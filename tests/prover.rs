@@ -0,0 +1,56 @@
+//! real proofs with KZG on bn256
+
+mod common;
+
+use halo2_proofs::{pairing::bn256::Fr, plonk::{keygen_vk, Circuit}};
+use halo2_bubble_sort::circuit::{minimal_k, BubSortCircuit, Visibility};
+use halo2_bubble_sort::prover;
+use common::values;
+
+#[test]
+fn proof_verifies_and_binds_the_output() {
+    // a real proof with KZG on bn256, at the same k the mock prover needs
+    let mut circuit = BubSortCircuit::<Fr, 5>::padded(&values());
+    circuit.input = Visibility::Public;
+    circuit.output = Visibility::Public;
+    let public_inputs = circuit.public_inputs();
+    let params = prover::setup(minimal_k(&circuit, &[public_inputs.clone()]).unwrap());
+    let pk = prover::keygen(&params, &circuit.without_witnesses()).unwrap();
+    let proof = prover::prove(&params, &pk, circuit, &public_inputs).unwrap();
+    assert!(prover::verify(&params, pk.get_vk(), &proof, &public_inputs).is_ok());
+
+    // the proof does not verify for a tampered output
+    let mut tampered = public_inputs;
+    tampered.swap(1 + 5, 1 + 5 + 1);
+    assert!(prover::verify(&params, pk.get_vk(), &proof, &tampered).is_err());
+}
+
+#[test]
+fn keys_only_depend_on_the_shape() {
+    let [a, b, c, d, e] = values();
+    let mut circuit = BubSortCircuit::<Fr, 5>::padded(&values());
+    circuit.input = Visibility::Public;
+    circuit.output = Visibility::Public;
+    let params = prover::setup(minimal_k(&circuit, &[circuit.public_inputs()]).unwrap());
+    // arrays of any content and length, and no array at all, give the same verifying key
+    let vk = keygen_vk(&params, &circuit.without_witnesses()).unwrap();
+    for values in [vec![e, d, c, b, a], vec![Fr::from(255), Fr::zero()], vec![]] {
+        let mut other = BubSortCircuit::<Fr, 5>::padded(&values);
+        other.input = Visibility::Public;
+        other.output = Visibility::Public;
+        let other = keygen_vk(&params, &other).unwrap();
+        assert_eq!(format!("{:?}", vk), format!("{:?}", other));
+    }
+
+    // but the order and the encoding are part of the keys
+    let mut descending = BubSortCircuit::<Fr, 5, 8, true>::padded(&[a, b, c, d, e]);
+    descending.input = Visibility::Public;
+    descending.output = Visibility::Public;
+    let other = keygen_vk(&params, &descending).unwrap();
+    assert_ne!(format!("{:?}", vk), format!("{:?}", other));
+    let mut signed = BubSortCircuit::<Fr, 5, 8, false, true>::padded(&[a, -b, c, -d, e]);
+    signed.input = Visibility::Public;
+    signed.output = Visibility::Public;
+    let other = keygen_vk(&params, &signed).unwrap();
+    assert_ne!(format!("{:?}", vk), format!("{:?}", other));
+}
@@ -0,0 +1,58 @@
+//! records whose payload moves along with the key
+
+mod common;
+
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_bubble_sort::circuit::Visibility;
+use halo2_bubble_sort::network::SortingNetwork;
+use halo2_bubble_sort::record::RecordSortCircuit;
+use common::{mock_prover, values};
+
+fn records() -> [(Fr, [Fr; 2]); 5] {
+    let [a, _, c, _, e] = values();
+    [
+        (c, [Fr::from(1), Fr::from(10)]),
+        (a, [Fr::from(2), Fr::from(20)]),
+        (e, [Fr::from(3), Fr::from(30)]),
+        (a, [Fr::from(4), Fr::from(40)]),
+        (Fr::from(255), [-Fr::one(), Fr::from(50)]),
+    ]
+}
+
+#[test]
+fn payloads_move_with_their_keys() {
+    // in both orders and through every network
+    let [_, _, c, _, e] = values();
+    for network in [SortingNetwork::Bubble, SortingNetwork::OddEvenMerge, SortingNetwork::Bitonic] {
+        let mut circuit = RecordSortCircuit::<Fr, 7, 2>::padded(&records());
+        circuit.output = Visibility::Public;
+        circuit.network = network;
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(public_inputs[1..7], [e, Fr::from(3), Fr::from(30), c, Fr::from(1), Fr::from(10)]);
+        let prover = mock_prover(&circuit, public_inputs.clone());
+        assert_eq!(prover.verify(), Ok(()));
+
+        // a key exposed with the payload of another record is rejected
+        let mut tampered = public_inputs;
+        tampered.swap(1 + 1, 1 + 3 + 1);
+        let prover = mock_prover(&circuit, tampered);
+        assert!(prover.verify().is_err());
+
+        let mut circuit = RecordSortCircuit::<Fr, 7, 2, 8, true, false, true>::padded(&records());
+        circuit.input = Visibility::Committed;
+        circuit.output = Visibility::Public;
+        circuit.network = network;
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(public_inputs[2..5], [Fr::from(255), -Fr::one(), Fr::from(50)]);
+        let prover = mock_prover(&circuit, public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[test]
+fn padding_slots_carry_an_empty_payload() {
+    let mut circuit = RecordSortCircuit::<Fr, 7, 2>::padded(&records());
+    circuit.payloads.as_mut().unwrap()[6][0] = Fr::one();
+    let prover = mock_prover(&circuit, circuit.public_inputs());
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,117 @@
+//! params, verifying keys and proofs written to files and read back
+
+mod common;
+
+use halo2_proofs::{pairing::bn256::Fr, plonk::Circuit};
+use halo2_bubble_sort::circuit::{minimal_k, BubSortCircuit, Visibility};
+use halo2_bubble_sort::prover;
+use halo2_bubble_sort::serialization::{self, CircuitKind, FormatError, Shape};
+use halo2_bubble_sort::transposition::TranspositionSortCircuit;
+use tempfile::TempDir;
+use common::values;
+
+type Sort = BubSortCircuit<Fr, 5>;
+
+const SHAPE: Shape = Shape { n: 5, payload: 0, bits: 8, descending: false, signed: false, committed: false };
+
+/// a proof of a public sort of 5 elements below 2^8, written to a fresh directory
+/// as sort.params, sort.vk and sort.proof
+fn written() -> (TempDir, Vec<u8>, Vec<Fr>) {
+    let mut circuit = Sort::padded(&values());
+    circuit.input = Visibility::Public;
+    circuit.output = Visibility::Public;
+    let public_inputs = circuit.public_inputs();
+    let params = prover::setup(minimal_k(&circuit, &[public_inputs.clone()]).unwrap());
+    let pk = prover::keygen(&params, &circuit.without_witnesses()).unwrap();
+    let proof = prover::prove(&params, &pk, circuit, &public_inputs).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    serialization::write_params(dir.path().join("sort.params"), &params).unwrap();
+    serialization::write_vk::<Sort>(dir.path().join("sort.vk"), pk.get_vk()).unwrap();
+    serialization::write_proof::<Sort>(dir.path().join("sort.proof"), &proof).unwrap();
+    (dir, proof, public_inputs)
+}
+
+#[test]
+fn round_trip() {
+    let (dir, proof, public_inputs) = written();
+    let params = serialization::read_params(dir.path().join("sort.params")).unwrap();
+    let vk = serialization::read_vk::<Sort>(dir.path().join("sort.vk"), &params).unwrap();
+    let read_proof = serialization::read_proof::<Sort>(dir.path().join("sort.proof")).unwrap();
+    assert_eq!(read_proof, proof);
+    assert!(prover::verify(&params, &vk, &read_proof, &public_inputs).is_ok());
+}
+
+#[test]
+fn other_shapes_are_refused() {
+    let (dir, _, _) = written();
+    let params = serialization::read_params(dir.path().join("sort.params")).unwrap();
+    let vk = dir.path().join("sort.vk");
+    assert!(matches!(
+        serialization::read_vk::<BubSortCircuit<Fr, 6>>(&vk, &params),
+        Err(FormatError::Shape { expected: Shape { n: 6, .. }, found: SHAPE }),
+    ));
+    assert!(matches!(
+        serialization::read_vk::<BubSortCircuit<Fr, 5, 16>>(&vk, &params),
+        Err(FormatError::Shape { expected: Shape { bits: 16, .. }, found: SHAPE }),
+    ));
+    assert!(matches!(
+        serialization::read_vk::<BubSortCircuit<Fr, 5, 8, true>>(&vk, &params),
+        Err(FormatError::Shape { expected: Shape { descending: true, .. }, found: SHAPE }),
+    ));
+    assert!(matches!(
+        serialization::read_vk::<BubSortCircuit<Fr, 5, 8, false, true>>(&vk, &params),
+        Err(FormatError::Shape { expected: Shape { signed: true, .. }, found: SHAPE }),
+    ));
+    assert!(matches!(
+        serialization::read_vk::<BubSortCircuit<Fr, 5, 8, false, false, true>>(&vk, &params),
+        Err(FormatError::Shape { expected: Shape { committed: true, .. }, found: SHAPE }),
+    ));
+    assert!(matches!(
+        serialization::read_proof::<BubSortCircuit<Fr, 5, 16>>(dir.path().join("sort.proof")),
+        Err(FormatError::Shape { .. }),
+    ));
+    assert!(matches!(serialization::read_params(&vk), Err(FormatError::UnexpectedKind { .. })));
+}
+
+#[test]
+fn other_circuits_are_refused() {
+    let (dir, _, _) = written();
+    let params = serialization::read_params(dir.path().join("sort.params")).unwrap();
+    assert!(matches!(
+        serialization::read_vk::<TranspositionSortCircuit<Fr, 5>>(dir.path().join("sort.vk"), &params),
+        Err(FormatError::UnexpectedCircuit { expected: CircuitKind::TranspositionSort, found: 0 }),
+    ));
+    assert!(matches!(
+        serialization::read_proof::<TranspositionSortCircuit<Fr, 5>>(dir.path().join("sort.proof")),
+        Err(FormatError::UnexpectedCircuit { .. }),
+    ));
+}
+
+#[test]
+fn truncated_proof_is_refused() {
+    let (dir, proof, _) = written();
+    let path = dir.path().join("sort.proof");
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    let claimed = proof.len() as u64;
+    assert!(matches!(
+        serialization::read_proof::<Sort>(&path),
+        Err(FormatError::ProofLength { claimed: c, available }) if c == claimed && available == claimed - 1,
+    ));
+}
+
+#[test]
+fn oversized_proof_length_is_refused() {
+    let (dir, proof, _) = written();
+    let path = dir.path().join("sort.proof");
+    // the length prefix is the 8 bytes in front of the proof
+    let mut bytes = std::fs::read(&path).unwrap();
+    let at = bytes.len() - proof.len() - 8;
+    bytes[at..at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+        serialization::read_proof::<Sort>(&path),
+        Err(FormatError::ProofLength { claimed: u64::MAX, .. }),
+    ));
+}